@@ -1,13 +1,33 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 
+use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::AccountId;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
+use crate::{PauseState, RaffleMode, Role, SalePhase, TimestampSec, TokenSeriesId};
+
+pub const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+/// mint, transfer and burn
+pub const NEP171_V1_0_0: &str = "1.0.0";
+/// adds contract_metadata_update and nft_metadata_update
+pub const NEP171_V1_2_0: &str = "1.2.0";
+/// version of the paras_series and paras_admin standards
+pub const PARAS_EVENT_VERSION: &str = "1.0.0";
+/// Budget for the logs of one call that go through its `EventBuffer`. NEAR caps all logs
+/// of a receipt at 16KB in total, this leaves room for logs from outside the contract code.
+pub const MAX_BUFFERED_EVENTS_LEN: usize = 15_000;
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "standard")]
 #[serde(rename_all = "snake_case")]
 pub enum NearEvent {
     Nep171(Nep171Event),
+    ParasSeries(ParasSeriesEvent),
+    ParasAdmin(ParasAdminEvent),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -25,48 +45,384 @@ pub enum Nep171EventKind {
     NftMint(Vec<NftMintData>),
     NftTransfer(Vec<NftTransferData>),
     NftBurn(Vec<NftBurnData>),
+    ContractMetadataUpdate(Vec<ContractMetadataUpdateData>),
+    NftMetadataUpdate(Vec<NftMetadataUpdateData>),
 }
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NftMintData {
-    pub owner_id: String,
-    pub token_ids: Vec<String>,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
     pub memo: Option<String>,
 }
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NftTransferData {
-    pub authorized_id: Option<String>,
-    pub old_owner_id: String,
-    pub new_owner_id: String,
-    pub token_ids: Vec<String>,
+    #[serde(default, deserialize_with = "valid_account_id::option")]
+    pub authorized_id: Option<AccountId>,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub old_owner_id: AccountId,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub new_owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
     pub memo: Option<String>,
 }
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NftBurnData {
-    pub authorized_id: Option<String>,
-    pub owner_id: String,
-    pub token_ids: Vec<String>,
+    #[serde(default, deserialize_with = "valid_account_id::option")]
+    pub authorized_id: Option<AccountId>,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
+    pub memo: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ContractMetadataUpdateData {
+    pub memo: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftMetadataUpdateData {
+    pub token_ids: Vec<TokenId>,
     pub memo: Option<String>,
 }
 
+/// Token series and minting actions that NEP-171 doesn't cover
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ParasSeriesEvent {
+    pub version: String,
+    #[serde(flatten)]
+    pub event_kind: ParasSeriesEventKind,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum ParasSeriesEventKind {
+    NftCreateSeries(Vec<NftCreateSeriesData>),
+    NftSetSeriesPrice(Vec<NftSetSeriesPriceData>),
+    NftDecreaseSeriesCopies(Vec<NftDecreaseSeriesCopiesData>),
+    NftSetSeriesNonMintable(Vec<NftSetSeriesNonMintableData>),
+    NftSetTokenMetadata(Vec<NftSetTokenMetadataData>),
+    NftUpdateSeriesMetadata(Vec<NftUpdateSeriesMetadataData>),
+    NftBuy(Vec<NftBuyData>),
+    CommitDraw(Vec<CommitDrawData>),
+    RaffleDraw(Vec<RaffleDrawData>),
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftCreateSeriesData {
+    pub token_series_id: TokenSeriesId,
+    pub token_metadata: TokenMetadata,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub creator_id: AccountId,
+    pub price: Option<U128>,
+    #[serde(deserialize_with = "valid_account_id::keys")]
+    pub royalty: HashMap<AccountId, u32>,
+    pub transaction_fee: String,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftSetSeriesPriceData {
+    pub token_series_id: TokenSeriesId,
+    pub price: Option<U128>,
+    pub transaction_fee: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftDecreaseSeriesCopiesData {
+    pub token_series_id: TokenSeriesId,
+    pub copies: U64,
+    pub is_non_mintable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftSetSeriesNonMintableData {
+    pub token_series_id: TokenSeriesId,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftSetTokenMetadataData {
+    pub token_id: TokenId,
+    pub metadata: Option<TokenMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftUpdateSeriesMetadataData {
+    pub token_series_id: TokenSeriesId,
+    pub metadata: TokenMetadata,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NftBuyData {
+    pub token_series_id: TokenSeriesId,
+    pub token_id: TokenId,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub buyer_id: AccountId,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub receiver_id: AccountId,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub creator_id: AccountId,
+    pub price: String,
+    pub transaction_fee: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CommitDrawData {
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub account_id: AccountId,
+    pub commitment: Base64VecU8,
+    pub block_height: U64,
+}
+
+/// Every input of a raffle draw, so the distribution can be audited off-chain
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RaffleDrawData {
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub account_id: AccountId,
+    pub block_height: U64,
+    pub random_seed: Base64VecU8,
+    pub nonce: U64,
+    pub secret: Base64VecU8,
+    pub raffle_mode: RaffleMode,
+    pub pool_size: U64,
+    pub drawn: U64,
+    pub token_series_id: TokenSeriesId,
+}
+
+/// Owner and role holder actions on the contract configuration
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ParasAdminEvent {
+    pub version: String,
+    #[serde(flatten)]
+    pub event_kind: ParasAdminEventKind,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum ParasAdminEventKind {
+    Migrate(Vec<MigrateData>),
+    StageCode(Vec<StageCodeData>),
+    ApproveCodeHash(Vec<CodeHashData>),
+    Upgrade(Vec<CodeHashData>),
+    GrantRole(Vec<RoleData>),
+    RevokeRole(Vec<RoleData>),
+    SetPauseState(Vec<SetPauseStateData>),
+    ProposeOwner(Vec<OwnerProposalData>),
+    CancelOwnerProposal(Vec<OwnerProposalData>),
+    AcceptOwnership(Vec<AcceptOwnershipData>),
+    SetTransactionFee(Vec<SetTransactionFeeData>),
+    SetTreasury(Vec<SetTreasuryData>),
+    SetMintPrice(Vec<SetMintPriceData>),
+    SetBalanceMintOg(Vec<SetBalanceMintOgData>),
+    AddOgAccountIds(Vec<AddOgAccountIdsData>),
+    RemoveOgAccountIds(Vec<RemoveOgAccountIdsData>),
+    SetOgMerkleRoot(Vec<SetOgMerkleRootData>),
+    SetSalePhases(Vec<SetSalePhasesData>),
+    SetMaxMintPerAccount(Vec<SetMaxMintPerAccountData>),
+    RaffleAddSeries(Vec<RaffleSeriesData>),
+    RaffleReserveSeries(Vec<RaffleSeriesData>),
+    RaffleIndexPositions(Vec<RaffleIndexPositionsData>),
+    SetRaffleMode(Vec<SetRaffleModeData>),
+    SetRaffleWeights(Vec<SetRaffleWeightsData>),
+    SetEditionTitle(Vec<SetEditionTitleData>),
+    SetPlaceholderMetadata(Vec<SetPlaceholderMetadataData>),
+    SetProvenanceHash(Vec<SetProvenanceHashData>),
+    Reveal(Vec<RevealData>),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MigrateData {
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StageCodeData {
+    pub code_hash: Base64VecU8,
+    pub code_len: U64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CodeHashData {
+    pub code_hash: Base64VecU8,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RoleData {
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub account_id: AccountId,
+    pub role: Role,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub sender_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetPauseStateData {
+    pub pause_state: PauseState,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub sender_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OwnerProposalData {
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub owner_id: AccountId,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub pending_owner_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AcceptOwnershipData {
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub old_owner_id: AccountId,
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub owner_id: AccountId,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetTransactionFeeData {
+    pub next_fee: u16,
+    pub start_time: Option<TimestampSec>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetTreasuryData {
+    #[serde(deserialize_with = "valid_account_id::deserialize")]
+    pub treasury_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetMintPriceData {
+    pub price_og: U128,
+    pub price_public: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetBalanceMintOgData {
+    pub balance_mint_og: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AddOgAccountIdsData {
+    #[serde(deserialize_with = "valid_account_id::keys")]
+    pub accounts: HashMap<AccountId, u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RemoveOgAccountIdsData {
+    #[serde(deserialize_with = "valid_account_id::vec")]
+    pub account_ids: Vec<AccountId>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetOgMerkleRootData {
+    pub merkle_root: Option<Base64VecU8>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetSalePhasesData {
+    pub sale_phases: Vec<SalePhase>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetMaxMintPerAccountData {
+    pub max_mint_per_account: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RaffleSeriesData {
+    pub token_series_ids: Vec<TokenSeriesId>,
+    pub raffle_length: U64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RaffleIndexPositionsData {
+    pub indexes_left: U64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetRaffleModeData {
+    pub raffle_mode: RaffleMode,
+}
+
+/// `None` weights went back to the remaining copies
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetRaffleWeightsData {
+    pub weights: HashMap<TokenSeriesId, Option<u32>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetEditionTitleData {
+    pub edition_title: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetPlaceholderMetadataData {
+    pub placeholder: TokenMetadata,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetProvenanceHashData {
+    pub provenance_hash: Base64VecU8,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RevealData {
+    pub base_uri: String,
+    pub provenance_hash: Base64VecU8,
+}
+
 impl Display for NearEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("EVENT_JSON:{}", self.to_json_string()))
+        f.write_str(&format!("{}{}", EVENT_JSON_PREFIX, self.to_json_string()))
     }
 }
 
 impl NearEvent {
     pub fn new_171(version: String, event_kind: Nep171EventKind) -> Self {
-        NearEvent::Nep171(Nep171Event { version, event_kind })
+        NearEvent::Nep171(Nep171Event {
+            version,
+            event_kind,
+        })
     }
 
     pub fn new_171_v1(event_kind: Nep171EventKind) -> Self {
-        NearEvent::new_171("1.0.0".to_string(), event_kind)
+        NearEvent::new_171(NEP171_V1_0_0.to_string(), event_kind)
+    }
+
+    pub fn new_171_v1_2(event_kind: Nep171EventKind) -> Self {
+        NearEvent::new_171(NEP171_V1_2_0.to_string(), event_kind)
+    }
+
+    /// Parses a log line written by `log`, `None` if it isn't one of our events or
+    /// carries an invalid account id
+    pub fn from_log_line(line: &str) -> Option<Self> {
+        let json = line.strip_prefix(EVENT_JSON_PREFIX)?;
+        serde_json::from_str(json).ok()
+    }
+
+    pub fn version(&self) -> &str {
+        match self {
+            NearEvent::Nep171(event) => &event.version,
+            NearEvent::ParasSeries(event) => &event.version,
+            NearEvent::ParasAdmin(event) => &event.version,
+        }
     }
 
     pub fn nft_burn(data: Vec<NftBurnData>) -> Self {
@@ -80,6 +436,28 @@ impl NearEvent {
         NearEvent::new_171_v1(Nep171EventKind::NftMint(data))
     }
 
+    pub fn contract_metadata_update(data: Vec<ContractMetadataUpdateData>) -> Self {
+        NearEvent::new_171_v1_2(Nep171EventKind::ContractMetadataUpdate(data))
+    }
+
+    pub fn nft_metadata_update(data: Vec<NftMetadataUpdateData>) -> Self {
+        NearEvent::new_171_v1_2(Nep171EventKind::NftMetadataUpdate(data))
+    }
+
+    pub fn paras_series(event_kind: ParasSeriesEventKind) -> Self {
+        NearEvent::ParasSeries(ParasSeriesEvent {
+            version: PARAS_EVENT_VERSION.to_string(),
+            event_kind,
+        })
+    }
+
+    pub fn paras_admin(event_kind: ParasAdminEventKind) -> Self {
+        NearEvent::ParasAdmin(ParasAdminEvent {
+            version: PARAS_EVENT_VERSION.to_string(),
+            event_kind,
+        })
+    }
+
     pub(crate) fn to_json_string(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
@@ -88,8 +466,12 @@ impl NearEvent {
         near_sdk::env::log(&self.to_string().as_bytes());
     }
 
-    pub fn log_nft_mint(owner_id: String, token_ids: Vec<String>, memo: Option<String>) {
-        NearEvent::log_nft_mints(vec![NftMintData { owner_id, token_ids, memo }]);
+    pub fn log_nft_mint(owner_id: AccountId, token_ids: Vec<TokenId>, memo: Option<String>) {
+        NearEvent::log_nft_mints(vec![NftMintData {
+            owner_id,
+            token_ids,
+            memo,
+        }]);
     }
 
     pub fn log_nft_mints(data: Vec<NftMintData>) {
@@ -97,11 +479,11 @@ impl NearEvent {
     }
 
     pub fn log_nft_transfer(
-        old_owner_id: String,
-        new_owner_id: String,
-        token_ids: Vec<String>,
+        old_owner_id: AccountId,
+        new_owner_id: AccountId,
+        token_ids: Vec<TokenId>,
         memo: Option<String>,
-        authorized_id: Option<String>,
+        authorized_id: Option<AccountId>,
     ) {
         NearEvent::log_nft_transfers(vec![NftTransferData {
             authorized_id,
@@ -117,22 +499,283 @@ impl NearEvent {
     }
 
     pub fn log_nft_burn(
-        owner_id: String,
-        token_ids: Vec<String>,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
         memo: Option<String>,
-        authorized_id: Option<String>,
+        authorized_id: Option<AccountId>,
     ) {
-        NearEvent::log_nft_burns(vec![NftBurnData { owner_id, authorized_id, token_ids, memo }]);
+        NearEvent::log_nft_burns(vec![NftBurnData {
+            owner_id,
+            authorized_id,
+            token_ids,
+            memo,
+        }]);
     }
 
     pub fn log_nft_burns(data: Vec<NftBurnData>) {
         NearEvent::nft_burn(data).log();
     }
+
+    pub fn log_contract_metadata_update(memo: Option<String>) {
+        NearEvent::contract_metadata_update(vec![ContractMetadataUpdateData { memo }]).log();
+    }
+
+    pub fn log_nft_metadata_update(token_ids: Vec<TokenId>, memo: Option<String>) {
+        NearEvent::nft_metadata_update(vec![NftMetadataUpdateData { token_ids, memo }]).log();
+    }
+}
+
+/// `AccountId` is a plain `String` in near-sdk 3.1, so the event types check the account
+/// ids they read back the way `ValidAccountId` does
+mod valid_account_id {
+    use std::collections::HashMap;
+
+    use near_sdk::env::is_valid_account_id;
+    use near_sdk::AccountId;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    fn check<E: Error>(account_id: AccountId) -> Result<AccountId, E> {
+        if is_valid_account_id(account_id.as_bytes()) {
+            Ok(account_id)
+        } else {
+            Err(E::custom(format!("invalid account id {}", account_id)))
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AccountId, D::Error> {
+        check(AccountId::deserialize(deserializer)?)
+    }
+
+    pub fn option<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<AccountId>, D::Error> {
+        Option::<AccountId>::deserialize(deserializer)?
+            .map(check)
+            .transpose()
+    }
+
+    pub fn vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<AccountId>, D::Error> {
+        Vec::<AccountId>::deserialize(deserializer)?
+            .into_iter()
+            .map(check)
+            .collect()
+    }
+
+    pub fn keys<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<AccountId, u32>, D::Error> {
+        HashMap::<AccountId, u32>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(account_id, value)| Ok((check(account_id)?, value)))
+            .collect()
+    }
+}
+
+/// Mint, transfer and burn data collected during one call. Entries with the same accounts
+/// and memo are merged, and every kind is logged as a single event when the buffer is
+/// dropped, which happens once the call is done with the contract.
+///
+/// Other events of a call that buffers something go through `log`, so they count against
+/// the same `max_len`. Panics as soon as the total would pass it, so a call touching too
+/// many tokens fails with a clear error instead of hitting the receipt log limit. Splitting
+/// long events into more logs would not help, the limit is for the whole receipt.
+pub struct EventBuffer {
+    max_len: usize,
+    /// length of everything buffered or logged so far, an upper bound
+    len: usize,
+    mints: Vec<NftMintData>,
+    transfers: Vec<NftTransferData>,
+    burns: Vec<NftBurnData>,
+}
+
+impl Default for EventBuffer {
+    fn default() -> Self {
+        EventBuffer::new(MAX_BUFFERED_EVENTS_LEN)
+    }
+}
+
+impl EventBuffer {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            len: 0,
+            mints: vec![],
+            transfers: vec![],
+            burns: vec![],
+        }
+    }
+
+    pub fn nft_mint(&mut self, owner_id: AccountId, token_ids: Vec<TokenId>, memo: Option<String>) {
+        let added = push_merged(
+            &mut self.mints,
+            NftMintData {
+                owner_id,
+                token_ids,
+                memo,
+            },
+            NearEvent::nft_mint,
+        );
+        self.add_len(added);
+    }
+
+    pub fn nft_transfer(
+        &mut self,
+        old_owner_id: AccountId,
+        new_owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        memo: Option<String>,
+        authorized_id: Option<AccountId>,
+    ) {
+        let added = push_merged(
+            &mut self.transfers,
+            NftTransferData {
+                authorized_id,
+                old_owner_id,
+                new_owner_id,
+                token_ids,
+                memo,
+            },
+            NearEvent::nft_transfer,
+        );
+        self.add_len(added);
+    }
+
+    pub fn nft_burn(
+        &mut self,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        memo: Option<String>,
+        authorized_id: Option<AccountId>,
+    ) {
+        let added = push_merged(
+            &mut self.burns,
+            NftBurnData {
+                owner_id,
+                authorized_id,
+                token_ids,
+                memo,
+            },
+            NearEvent::nft_burn,
+        );
+        self.add_len(added);
+    }
+
+    /// Logs `event` right away
+    pub fn log(&mut self, event: NearEvent) {
+        let log = event.to_string();
+        self.add_len(log.len());
+        near_sdk::env::log(log.as_bytes());
+    }
+
+    /// Logs mints, then transfers, then burns
+    fn flush(&mut self) {
+        log_all(std::mem::take(&mut self.mints), NearEvent::nft_mint);
+        log_all(std::mem::take(&mut self.transfers), NearEvent::nft_transfer);
+        log_all(std::mem::take(&mut self.burns), NearEvent::nft_burn);
+    }
+
+    fn add_len(&mut self, added: usize) {
+        self.len += added;
+        assert!(
+            self.len <= self.max_len,
+            "Paras: events of one call can't pass {} bytes, use fewer tokens per call",
+            self.max_len
+        );
+    }
+}
+
+impl Drop for EventBuffer {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Event data that lists token ids for one set of accounts
+trait TokenIdsData: Serialize {
+    fn same_accounts(&self, other: &Self) -> bool;
+    fn token_ids_mut(&mut self) -> &mut Vec<TokenId>;
+}
+
+impl TokenIdsData for NftMintData {
+    fn same_accounts(&self, other: &Self) -> bool {
+        self.owner_id == other.owner_id && self.memo == other.memo
+    }
+
+    fn token_ids_mut(&mut self) -> &mut Vec<TokenId> {
+        &mut self.token_ids
+    }
+}
+
+impl TokenIdsData for NftTransferData {
+    fn same_accounts(&self, other: &Self) -> bool {
+        self.old_owner_id == other.old_owner_id
+            && self.new_owner_id == other.new_owner_id
+            && self.authorized_id == other.authorized_id
+            && self.memo == other.memo
+    }
+
+    fn token_ids_mut(&mut self) -> &mut Vec<TokenId> {
+        &mut self.token_ids
+    }
+}
+
+impl TokenIdsData for NftBurnData {
+    fn same_accounts(&self, other: &Self) -> bool {
+        self.owner_id == other.owner_id
+            && self.authorized_id == other.authorized_id
+            && self.memo == other.memo
+    }
+
+    fn token_ids_mut(&mut self) -> &mut Vec<TokenId> {
+        &mut self.token_ids
+    }
+}
+
+fn json_len<T: Serialize>(value: &T) -> usize {
+    serde_json::to_string(value).unwrap().len()
+}
+
+/// Adds `data` to the latest entry for the same accounts and returns how much longer
+/// the logged event gets
+fn push_merged<T: TokenIdsData>(
+    entries: &mut Vec<T>,
+    mut data: T,
+    to_event: fn(Vec<T>) -> NearEvent,
+) -> usize {
+    if let Some(entry) = entries
+        .iter_mut()
+        .rev()
+        .find(|entry| entry.same_accounts(&data))
+    {
+        // one more for each comma
+        let added = data
+            .token_ids_mut()
+            .iter()
+            .map(|token_id| json_len(token_id) + 1)
+            .sum();
+        entry.token_ids_mut().append(data.token_ids_mut());
+        return added;
+    }
+    let mut added = json_len(&data) + 1;
+    if entries.is_empty() {
+        added += to_event(vec![]).to_string().len();
+    }
+    entries.push(data);
+    added
+}
+
+fn log_all<T>(data: Vec<T>, to_event: fn(Vec<T>) -> NearEvent) {
+    if !data.is_empty() {
+        to_event(data).log();
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
 
     fn make_tokens(s_vec: Vec<&str>) -> Vec<String> {
         s_vec.iter().map(|t| t.to_string()).collect()
@@ -142,7 +785,11 @@ mod tests {
     fn nft_mint() {
         let owner_id = "bob".to_string();
         let token_ids = make_tokens(vec!["0", "1"]);
-        let mint_log = NftMintData { owner_id, token_ids, memo: None };
+        let mint_log = NftMintData {
+            owner_id,
+            token_ids,
+            memo: None,
+        };
         let event_log = NearEvent::nft_mint(vec![mint_log]);
         assert_eq!(
             serde_json::to_string(&event_log).unwrap(),
@@ -154,7 +801,11 @@ mod tests {
     fn nft_mints() {
         let owner_id = "bob".to_string();
         let token_ids = make_tokens(vec!["0", "1"]);
-        let mint_log = NftMintData { owner_id, token_ids, memo: None };
+        let mint_log = NftMintData {
+            owner_id,
+            token_ids,
+            memo: None,
+        };
         let event_log = NearEvent::nft_mint(vec![
             mint_log,
             NftMintData {
@@ -179,7 +830,7 @@ mod tests {
             token_ids,
             memo: None,
         }])
-            .to_json_string();
+        .to_json_string();
         assert_eq!(
             log,
             r#"{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"bob","token_ids":["0","1"]}]}"#
@@ -197,9 +848,14 @@ mod tests {
                 token_ids: make_tokens(vec!["2", "3"]),
                 memo: Some("has memo".to_string()),
             },
-            NftBurnData { owner_id, authorized_id: None, token_ids, memo: None },
+            NftBurnData {
+                owner_id,
+                authorized_id: None,
+                token_ids,
+                memo: None,
+            },
         ])
-            .to_json_string();
+        .to_json_string();
         assert_eq!(
             log,
             r#"{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"authorized_id":"4","owner_id":"alice","token_ids":["2","3"],"memo":"has memo"},{"owner_id":"bob","token_ids":["0","1"]}]}"#
@@ -218,7 +874,7 @@ mod tests {
             token_ids,
             memo: None,
         }])
-            .to_json_string();
+        .to_json_string();
         assert_eq!(
             log,
             r#"{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"alice","token_ids":["0","1"]}]}"#
@@ -246,10 +902,218 @@ mod tests {
                 memo: None,
             },
         ])
-            .to_json_string();
+        .to_json_string();
         assert_eq!(
             log,
             r#"{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"authorized_id":"4","old_owner_id":"alice","new_owner_id":"bob","token_ids":["2","3"],"memo":"has memo"},{"old_owner_id":"bob","new_owner_id":"alice","token_ids":["0","1"]}]}"#
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn contract_metadata_update() {
+        let log =
+            NearEvent::contract_metadata_update(vec![ContractMetadataUpdateData { memo: None }])
+                .to_json_string();
+        assert_eq!(
+            log,
+            r#"{"standard":"nep171","version":"1.2.0","event":"contract_metadata_update","data":[{}]}"#
+        );
+    }
+
+    #[test]
+    fn nft_metadata_update() {
+        let log = NearEvent::nft_metadata_update(vec![NftMetadataUpdateData {
+            token_ids: make_tokens(vec!["1:1", "1:2"]),
+            memo: None,
+        }])
+        .to_json_string();
+        assert_eq!(
+            log,
+            r#"{"standard":"nep171","version":"1.2.0","event":"nft_metadata_update","data":[{"token_ids":["1:1","1:2"]}]}"#
+        );
+    }
+
+    #[test]
+    fn paras_series_set_price() {
+        let log = NearEvent::paras_series(ParasSeriesEventKind::NftSetSeriesPrice(vec![
+            NftSetSeriesPriceData {
+                token_series_id: "1".to_string(),
+                price: Some(U128(1_000)),
+                transaction_fee: "500".to_string(),
+            },
+            NftSetSeriesPriceData {
+                token_series_id: "2".to_string(),
+                price: None,
+                transaction_fee: "500".to_string(),
+            },
+        ]))
+        .to_json_string();
+        assert_eq!(
+            log,
+            r#"{"standard":"paras_series","version":"1.0.0","event":"nft_set_series_price","data":[{"token_series_id":"1","price":"1000","transaction_fee":"500"},{"token_series_id":"2","transaction_fee":"500"}]}"#
+        );
+    }
+
+    #[test]
+    fn paras_admin_set_treasury() {
+        let log = NearEvent::paras_admin(ParasAdminEventKind::SetTreasury(vec![SetTreasuryData {
+            treasury_id: "treasury".to_string(),
+        }]))
+        .to_json_string();
+        assert_eq!(
+            log,
+            r#"{"standard":"paras_admin","version":"1.0.0","event":"set_treasury","data":[{"treasury_id":"treasury"}]}"#
+        );
+    }
+
+    #[test]
+    fn from_log_line_nep171() {
+        let event = NearEvent::nft_mint(vec![NftMintData {
+            owner_id: "bob".to_string(),
+            token_ids: make_tokens(vec!["1:1"]),
+            memo: None,
+        }]);
+        let parsed = NearEvent::from_log_line(&event.to_string()).unwrap();
+        assert_eq!(parsed.version(), NEP171_V1_0_0);
+        match parsed {
+            NearEvent::Nep171(Nep171Event {
+                event_kind: Nep171EventKind::NftMint(data),
+                ..
+            }) => {
+                assert_eq!(data[0].owner_id, "bob");
+                assert_eq!(data[0].token_ids, make_tokens(vec!["1:1"]));
+                assert!(data[0].memo.is_none());
+            }
+            _ => panic!("expected nft_mint"),
+        }
+    }
+
+    #[test]
+    fn from_log_line_metadata_update() {
+        let line = r#"EVENT_JSON:{"standard":"nep171","version":"1.2.0","event":"nft_metadata_update","data":[{"token_ids":["1:1","1:2"]}]}"#;
+        match NearEvent::from_log_line(line).unwrap() {
+            NearEvent::Nep171(Nep171Event {
+                version,
+                event_kind: Nep171EventKind::NftMetadataUpdate(data),
+            }) => {
+                assert_eq!(version, NEP171_V1_2_0);
+                assert_eq!(data[0].token_ids, make_tokens(vec!["1:1", "1:2"]));
+            }
+            _ => panic!("expected nft_metadata_update"),
+        }
+    }
+
+    #[test]
+    fn from_log_line_paras_admin() {
+        let event =
+            NearEvent::paras_admin(ParasAdminEventKind::SetTreasury(vec![SetTreasuryData {
+                treasury_id: "treasury".to_string(),
+            }]));
+        match NearEvent::from_log_line(&event.to_string()).unwrap() {
+            NearEvent::ParasAdmin(ParasAdminEvent {
+                event_kind: ParasAdminEventKind::SetTreasury(data),
+                ..
+            }) => assert_eq!(data[0].treasury_id, "treasury"),
+            _ => panic!("expected set_treasury"),
+        }
+    }
+
+    #[test]
+    fn from_log_line_transfer_without_authorized_id() {
+        let line = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"alice","token_ids":["1:1"]}]}"#;
+        match NearEvent::from_log_line(line).unwrap() {
+            NearEvent::Nep171(Nep171Event {
+                event_kind: Nep171EventKind::NftTransfer(data),
+                ..
+            }) => {
+                assert_eq!(data[0].new_owner_id, "alice");
+                assert!(data[0].authorized_id.is_none());
+            }
+            _ => panic!("expected nft_transfer"),
+        }
+    }
+
+    #[test]
+    fn from_log_line_rejects_invalid_account_ids() {
+        let lines = vec![
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"Bob!","token_ids":["1:1"]}]}"#,
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"authorized_id":"4","owner_id":"bob","token_ids":["1:1"]}]}"#,
+            r#"EVENT_JSON:{"standard":"paras_admin","version":"1.0.0","event":"remove_og_account_ids","data":[{"account_ids":["bob","a..b"]}]}"#,
+            r#"EVENT_JSON:{"standard":"paras_admin","version":"1.0.0","event":"add_og_account_ids","data":[{"accounts":{"bob":1,"":2}}]}"#,
+        ];
+        for line in lines {
+            assert!(NearEvent::from_log_line(line).is_none(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn from_log_line_ignores_other_logs() {
+        assert!(NearEvent::from_log_line("Transfer 10 from alice").is_none());
+        assert!(NearEvent::from_log_line(r#"EVENT_JSON:{"standard":"nep141"}"#).is_none());
+    }
+
+    #[test]
+    fn event_buffer_merges_entries() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut events = EventBuffer::default();
+        events.nft_mint("bob".to_string(), make_tokens(vec!["1:1"]), None);
+        events.nft_transfer(
+            "bob".to_string(),
+            "alice".to_string(),
+            make_tokens(vec!["1:1"]),
+            None,
+            None,
+        );
+        events.nft_mint("bob".to_string(), make_tokens(vec!["2:1"]), None);
+        events.nft_mint("alice".to_string(), make_tokens(vec!["3:1"]), None);
+        drop(events);
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"bob","token_ids":["1:1","2:1"]},{"owner_id":"alice","token_ids":["3:1"]}]}"#,
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{"old_owner_id":"bob","new_owner_id":"alice","token_ids":["1:1"]}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn event_buffer_len_bounds_logs() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut events = EventBuffer::default();
+        for edition in 1..=50 {
+            events.nft_mint("a".repeat(64), vec![format!("1:{}", edition)], None);
+            // a different owner every time, nothing to merge
+            let token_ids = vec![format!("2:{}", edition)];
+            events.nft_burn(format!("owner-{}", edition), token_ids, None, None);
+        }
+        events.log(NearEvent::paras_admin(
+            ParasAdminEventKind::SetEditionTitle(vec![SetEditionTitleData {
+                edition_title: true,
+            }]),
+        ));
+        let len = events.len;
+        drop(events);
+
+        let logs_len: usize = get_logs().iter().map(|log| log.len()).sum();
+        assert_eq!(get_logs().len(), 3);
+        assert!(
+            logs_len <= len,
+            "{} bytes logged, {} counted",
+            logs_len,
+            len
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Paras: events of one call can't pass 1000 bytes, use fewer tokens per call"
+    )]
+    fn event_buffer_panics_past_max_len() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut events = EventBuffer::new(1_000);
+        for edition in 1..=120 {
+            events.nft_mint("a".repeat(64), vec![format!("1:{}", edition)], None);
+        }
+    }
+}
@@ -7,23 +7,35 @@ use near_contract_standards::non_fungible_token::metadata::{
 use near_contract_standards::non_fungible_token::NonFungibleToken;
 use near_contract_standards::non_fungible_token::{Token, TokenId};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, UnorderedMap, UnorderedSet};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::env::is_valid_account_id;
-use near_sdk::json_types::{ValidAccountId, U128, U64};
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    assert_one_yocto, env, ext_contract, near_bindgen, serde_json::json, AccountId, Balance,
-    BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue, Timestamp,
+    assert_one_yocto, env, ext_contract, near_bindgen, AccountId, Balance, BorshStorageKey, Gas,
+    PanicOnDefault, Promise, PromiseOrValue, Timestamp,
 };
 use std::collections::HashMap;
-use std::convert::TryInto;
 use std::vec;
 
 pub mod event;
 pub use event::NearEvent;
+use event::{EventBuffer, ParasAdminEventKind, ParasSeriesEventKind};
 
 mod raffle;
-use raffle::Raffle;
+pub use raffle::RaffleMode;
+use raffle::{Raffle, RaffleV1};
+
+mod roles;
+pub use roles::Role;
+
+mod merkle;
+pub use merkle::MerkleProof;
+
+mod phase;
+pub use phase::{PhaseEligibility, SalePhase};
+
+mod royalty;
 
 /// between token_series_id and edition number e.g. 42:2 where 42 is series and 2 is edition
 pub const TOKEN_DELIMETER: char = ':';
@@ -36,8 +48,22 @@ const GAS_FOR_RESOLVE_TRANSFER: Gas = 10_000_000_000_000;
 const GAS_FOR_NFT_TRANSFER_CALL: Gas = 30_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER;
 const GAS_FOR_NFT_APPROVE: Gas = 10_000_000_000_000;
 const GAS_FOR_MINT: Gas = 90_000_000_000_000;
+const GAS_FOR_UPGRADE: Gas = 30_000_000_000_000;
+/// least gas left for `migrate` after `upgrade` deploys the new code
+const GAS_FOR_MIGRATE: Gas = 50_000_000_000_000;
+/// keeps `draw_and_mint_many` well within the gas limit of a single call
+const MAX_DRAW_PER_CALL: u32 = 10;
+/// weighted draws read every entry of the pool once per call
+const MAX_WEIGHTED_POOL_LEN: u64 = 100;
+/// token ids per `nft_metadata_update` log, a few KB out of the 16KB NEAR allows per receipt
+const MAX_METADATA_UPDATE_TOKEN_IDS: u64 = 300;
 const NO_DEPOSIT: Balance = 0;
 const MAX_PRICE: Balance = 1_000_000_000 * 10u128.pow(24);
+/// near-sdk key the contract struct is stored under
+const STATE_KEY: &[u8] = b"STATE";
+/// ContractV1 is 1 and the released ContractV2 is 2, everything added since ships as layout 3.
+/// Bump on every layout change after a release and teach `migrate` the previous one
+const STATE_VERSION: u32 = 3;
 
 pub type TokenSeriesId = String;
 pub type TimestampSec = u32;
@@ -98,6 +124,13 @@ pub struct TokenSeries {
     royalty: HashMap<AccountId, u32>,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OgAccountJson {
+    account_id: AccountId,
+    balance_mint_og: u32,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct TokenSeriesJson {
@@ -116,6 +149,39 @@ pub struct TransactionFee {
     pub current_fee: u16,
 }
 
+/// Price charged by `draw_and_mint` and `nft_mint`, paid out to `treasury_id`
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintPrice {
+    pub og: U128,
+    pub public: U128,
+}
+
+/// Circuit breakers, each one stops a group of entry points until unset
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseState {
+    pub minting: bool,
+    pub transfers: bool,
+    pub payouts: bool,
+}
+
+/// What a `draw_and_mint` / `nft_mint` call costs the caller and which counters it moves
+struct MintTerms {
+    /// per token
+    price: Balance,
+    quantity: u32,
+    use_og_mint: bool,
+    phase_id: Option<String>,
+}
+
+/// sha256 of a secret committed by `commit_draw`, revealed after `block_height`
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DrawCommitment {
+    commitment: Vec<u8>,
+    block_height: u64,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct MarketDataTransactionFee {
     pub transaction_fee: UnorderedMap<TokenSeriesId, u128>,
@@ -133,6 +199,24 @@ pub struct ContractV1 {
     transaction_fee: TransactionFee,
 }
 
+/// Layout of the released contract, before state versions
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct ContractV2 {
+    tokens: NonFungibleToken,
+    metadata: LazyOption<NFTContractMetadata>,
+    // CUSTOM
+    token_series_by_id: UnorderedMap<TokenSeriesId, TokenSeries>,
+    seller_by_id: UnorderedMap<AccountId, u128>,
+    raffle: RaffleV1,
+    token_series_id_minted: u128,
+    treasury_id: AccountId,
+    whitelist_contract_id: AccountId,
+    transaction_fee: TransactionFee,
+    account_id_og: HashMap<AccountId, u32>,
+    balance_mint_og: u32,
+    market_data_transaction_fee: MarketDataTransactionFee,
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
@@ -144,11 +228,46 @@ pub struct Contract {
     raffle: Raffle,
     token_series_id_minted: u128,
     treasury_id: AccountId,
-    whitelist_contract_id: AccountId,
+    /// `None` for contracts migrated from V1, which never had one
+    whitelist_contract_id: Option<AccountId>,
     transaction_fee: TransactionFee,
-    account_id_og: HashMap<AccountId, u32>,
+    account_id_og: UnorderedMap<AccountId, u32>,
     balance_mint_og: u32,
     market_data_transaction_fee: MarketDataTransactionFee,
+    mint_price: MintPrice,
+    state_version: u32,
+    staged_code_hash: Option<Vec<u8>>,
+    approved_code_hash: Option<Vec<u8>>,
+    roles: LookupMap<AccountId, Vec<Role>>,
+    pending_owner_id: Option<AccountId>,
+    pause_state: PauseState,
+    og_merkle_root: Option<Vec<u8>>,
+    og_merkle_claimed: LookupMap<AccountId, u32>,
+    sale_phases: Vec<SalePhase>,
+    phase_minted: LookupMap<(String, AccountId), u32>,
+    minted_count: LookupMap<AccountId, u32>,
+    max_mint_per_account: Option<u32>,
+    draw_commitments: LookupMap<AccountId, DrawCommitment>,
+    /// raffle values below this were never mapped and stand for series "value + 1"
+    raffle_implicit_len: u64,
+    raffle_next_value: u64,
+    raffle_series_by_value: LookupMap<u64, TokenSeriesId>,
+    raffle_value_by_series: LookupMap<TokenSeriesId, u64>,
+    raffle_mode: RaffleMode,
+    /// overrides the default weight (remaining copies) in weighted mode
+    raffle_weights: LookupMap<TokenSeriesId, u32>,
+    /// shown instead of every series metadata until `reveal`
+    placeholder_metadata: LazyOption<TokenMetadata>,
+    revealed: bool,
+    /// sha256 of every series media_hash in creation order, frozen after the first mint
+    provenance_hash: Option<Vec<u8>>,
+    /// edition specific fields merged on top of the series metadata in `nft_token`
+    token_metadata_overrides: LookupMap<TokenId, TokenMetadata>,
+    /// formats token titles as "Title #N"
+    edition_title: bool,
+    /// mint, transfer and burn events of the current call, logged when the contract is dropped
+    #[borsh_skip]
+    events: EventBuffer,
 }
 
 const DATA_IMAGE_SVG_PARAS_ICON: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAQAAAAEACAMAAABrrFhUAAAABGdBTUEAALGPC/xhBQAAACBjSFJNAAB6JgAAgIQAAPoAAACA6AAAdTAAAOpgAAA6mAAAF3CculE8AAACWFBMVEUAAAANAAANAAANAAAXTRgNAAANAAANAAAZYB4NAAANAAANAAATLQ4NAAANAAANAAAQGAgNAAANAAALSQ4NAAANAAATLQ4MJQcUNRASCgAUMgwOLAwQAgENAAASCgAKAwANAAANAAANAAAPBAAMAQANAAANAAANAAANAAAQBgASCgAUQREQPREKAwALAgANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAANAAAkuzoYVRor8Usehioboy4X2TYfjiwe4T4HxSYXUBkdgCghoTIYrS4WRxYZYx8LRg0QGwgXWBsn7EcXbiAMFgQLTA8IryIHryIg5EAKfxkPAAAOAAAKAAAJfxkLXhIOFgQTTBQbrzIarzIm0kEo1kMUWhoKFwQJXxIMURAURhQcxTgp7EkWVhoKAQAJUhAQcBsT1DIPzy8LWREMQg0TaBsYgyUTcyAKVRcOYhwPZB0aiysgoTIXfSMKSg0JXxEKaBQOIwcXdCESiiYBHQYcpjMekywLDAAKLQcKbRUKbhUMchUTXBgVWBkZmCsCJgkNaBwctDUk3UMgsTUZciEWcR8IbRYNAQASCgAapC4EMQwi10Alxj4KAAEUNhERMw4MLAgMKggDLAsWoC0auDQlxj8MAAUMKQgIuiQmxkERABUIuSUS0jEq40caoy4QAgEp40cOLwsUNAwZiSYWhyYOLgwYeCEVdSEKAwAPBAATOQ8WXRkUZxwQeiIRgyQWfyQSWRkPNQ8MAQAQBgAWTBQQRxQLAgAXgyX///+6pbP5AAAAP3RSTlMAWnXG2pYqjtBLLVWHhxYclMNsyis5oqLzOdzcOfDS0jxxa9LS0gWbqqqqyMiqqg8o41Cteht4aYxGB4k6QSPyzJCnAAAAAWJLR0THjQVKWwAAAAd0SU1FB+YIEBcnNxex7P4AAASOSURBVHja7dzXe1RFHIfxXToLIaGjgiy9qCTUJLSFUIIrVYogRUQUxRIjKBYw9F4UVFR6lS4goCA2QP4ub3/f8GSeOYeTs2F931t2zsx8Zm/CnCSRiK1kxrtkfKuKMQAAAAAAAAAAAAAAAAAAgP8JQKPGtibjvGsiAxvleh+hayrHOr7Cu/EysGmu9wEAAAAAAAAAAAAAAAAAAJBINGvuXYsJtomTvJsoA1v4z9gsBoCWGe8my7FWTvGuUgZO9p+xJQAAAAAAAAAAAAAAAAAAQGSlWtlav+hd9iXbVH+AqTIw6z9ja1lqKiKAAkGeVuHddBk4wx9ghgyc7j/jNBlYAAAAAAAAAAAAAAAAAAAAEKg2hbaimbZZL3s3e45t7jzpFUn/ba4MnO0/4yxZapFso00AgLYCmRXk+f4HqS141bZwkSQTZhaHnWO+LDUrD20LAAAAAAAAAAAAAAAAAAAAQCwAS16zLX3dtkx7Y7ntzbdsK55YgLflzN9Z6ehdmeO9920fAAAAAAAAAAAAAAAAAAAA5AKg6kNbtWsBH62yrf7Y9skaR59+Zvv8C9vadbYvXfNXy1KrIgKo8T+B9XLmG+SQN27ybrMc5Jattm3+q6kBAAAAAAAAAAAAAAAAAAAIBNBO/jZN++11t2OntGu3bY8DYO8+R199bdt/wPbNt5JjbdvbyzbaBQDQ9GVp7buD0veOH/kV4IeV3v0o34dDhyXH2urpbXEAAAAAAAAAAAAAAAAAIH8BOnS0dTpiO3rMdjwswImTjk45AE6fkc7Kco7KUjvJNjoEAPC/Hf4pLICzjQ6Ac+elC7Kc+K/HAQAAAAAAAAAAAAAAAAAA8hZAb4cvyhyXYvj/AO3yFelnWc5FWWoNAAAAAAAAAAAAAAAAAAAAAEEA9HK0s9w/XpX7xy5PSU9fs12PBuCXG7ZnukpdZDlXZamdQ1+Oaq7r8W760Wfl+3AzGoBbv9q664zdHIuL4/0AAAAAAAAAAAAAAAAAACB/AdI96q6nfrTXb7bbd2y/37X94dryn3/Z/v7H1ltn7OlYXDoigAD1kRO4J9+H+/5n/kDO/F95aJ/4dwUAAAAAAAAAAAAAAAAAAFC7vq2kfv1tAwbannvo3fMv2AbJQ/vpjH1zDFAsp5NJOT6azHiXdDwmpR8tBgAAAAAAAAAAAAAAAAAAqK9NFtZdyWCpxPGYIYO9G+J4TO0ZHYuLCifA7XD8NbDrcQAAAAAAAAAAAAAAAAAAgCcZYKj8xDnM8RPn8FwDDHcsbphsY2iAp+qvzo7I9SbDNkK2Ef53hwHI9UYAAAAAAAAAAAAAAACg/gDkbdT+pfI2aon/lGXymPJo9lEuDy3zH1gi2yjVPdb6rFhl0mHXqi9Ll0YDUCoPDf2ydFr3CAAAAAAAAAAAAAAAAAAA8BgAI/UP14xqbhsdDcBoeegonXFkjgGKdWDKe2DoQr8sDQAAAAAAAAAAAAAAAAAA8ChAYynZ0bcxOnBs/QOM1RnHeC81qQOdk+jtsKsG/a6wFv56HAAAAAAAAAAAAAAAAAAAIO8A/gMi4F3WVIpUDAAAACV0RVh0ZGF0ZTpjcmVhdGUAMjAyMi0wOC0xNlQyMzozOTo1NSswMDowMNzR1DsAAAAldEVYdGRhdGU6bW9kaWZ5ADIwMjItMDgtMTZUMjM6Mzk6NTUrMDA6MDCtjGyHAAAAAElFTkSuQmCC";
@@ -167,6 +286,18 @@ enum StorageKey {
     MarketDataTransactionFee,
     SellerById,
     Raffle,
+    StagedCode,
+    Roles,
+    AccountIdOg,
+    OgMerkleClaimed,
+    PhaseMinted,
+    MintedCount,
+    DrawCommitments,
+    RaffleSeriesByValue,
+    RaffleValueBySeries,
+    RaffleWeights,
+    PlaceholderMetadata,
+    TokenMetadataOverrides,
 }
 
 #[near_bindgen]
@@ -221,8 +352,8 @@ impl Contract {
             seller_by_id: UnorderedMap::new(StorageKey::SellerById),
             raffle: Raffle::new(StorageKey::Raffle, max_supply_raffle as u64),
             token_series_id_minted: 0,
-            whitelist_contract_id: whitelist_contract_id,
-            account_id_og: HashMap::new(),
+            whitelist_contract_id: Some(whitelist_contract_id),
+            account_id_og: UnorderedMap::new(StorageKey::AccountIdOg),
             balance_mint_og: 0,
             transaction_fee: TransactionFee {
                 next_fee: None,
@@ -232,23 +363,310 @@ impl Contract {
             market_data_transaction_fee: MarketDataTransactionFee {
                 transaction_fee: UnorderedMap::new(StorageKey::MarketDataTransactionFee),
             },
+            mint_price: MintPrice {
+                og: U128(0),
+                public: U128(0),
+            },
+            state_version: STATE_VERSION,
+            staged_code_hash: None,
+            approved_code_hash: None,
+            roles: LookupMap::new(StorageKey::Roles),
+            pending_owner_id: None,
+            pause_state: PauseState::default(),
+            og_merkle_root: None,
+            og_merkle_claimed: LookupMap::new(StorageKey::OgMerkleClaimed),
+            sale_phases: vec![],
+            phase_minted: LookupMap::new(StorageKey::PhaseMinted),
+            minted_count: LookupMap::new(StorageKey::MintedCount),
+            max_mint_per_account: Some(1),
+            draw_commitments: LookupMap::new(StorageKey::DrawCommitments),
+            raffle_implicit_len: max_supply_raffle as u64,
+            raffle_next_value: max_supply_raffle as u64,
+            raffle_series_by_value: LookupMap::new(StorageKey::RaffleSeriesByValue),
+            raffle_value_by_series: LookupMap::new(StorageKey::RaffleValueBySeries),
+            raffle_mode: RaffleMode::Uniform,
+            raffle_weights: LookupMap::new(StorageKey::RaffleWeights),
+            placeholder_metadata: LazyOption::new(StorageKey::PlaceholderMetadata, None),
+            revealed: true,
+            provenance_hash: None,
+            token_metadata_overrides: LookupMap::new(StorageKey::TokenMetadataOverrides),
+            edition_title: false,
+            events: EventBuffer::default(),
+        }
+    }
+
+    /// Reads whatever layout is stored and returns it as the current one.
+    /// Layouts are tried newest first, so calling it on current state only bumps the version.
+    /// ContractV2 never stored the `max_supply_raffle` it was initialized with, so migrating it
+    /// takes that value again.
+    #[init(ignore_state)]
+    pub fn migrate(max_supply_raffle: Option<u32>) -> Self {
+        let state = env::storage_read(STATE_KEY).expect("Paras: contract is not initialized");
+
+        let mut contract = if let Ok(contract) = Contract::try_from_slice(&state) {
+            contract
+        } else if let Ok(prev) = ContractV2::try_from_slice(&state) {
+            let max_supply_raffle =
+                max_supply_raffle.expect("Paras: max_supply_raffle is required to migrate V2");
+            Contract::from_v2(prev, max_supply_raffle)
+        } else {
+            let prev = ContractV1::try_from_slice(&state).expect("Paras: unknown state layout");
+            Contract::from_v1(prev)
+        };
+
+        let predecessor = env::predecessor_account_id();
+        assert!(
+            predecessor == contract.tokens.owner_id || predecessor == env::current_account_id(),
+            "Paras: Owner only"
+        );
+
+        NearEvent::paras_admin(ParasAdminEventKind::Migrate(vec![event::MigrateData {
+            from_version: contract.state_version,
+            to_version: STATE_VERSION,
+        }]))
+        .log();
+
+        contract.state_version = STATE_VERSION;
+        contract
+    }
+
+    fn from_v1(prev: ContractV1) -> Self {
+        // V1 series ids are sequential, so every existing series goes back in the raffle pool
+        let raffle_len = prev.token_series_by_id.len();
+        let token_series_id_minted = prev.tokens.owner_by_id.len() as u128;
+
+        Self {
+            tokens: prev.tokens,
+            metadata: prev.metadata,
+            token_series_by_id: prev.token_series_by_id,
+            seller_by_id: UnorderedMap::new(StorageKey::SellerById),
+            raffle: Raffle::new(StorageKey::Raffle, raffle_len),
+            token_series_id_minted,
+            treasury_id: prev.treasury_id,
+            whitelist_contract_id: None,
+            transaction_fee: prev.transaction_fee,
+            account_id_og: UnorderedMap::new(StorageKey::AccountIdOg),
+            balance_mint_og: 0,
+            market_data_transaction_fee: MarketDataTransactionFee {
+                transaction_fee: UnorderedMap::new(StorageKey::MarketDataTransactionFee),
+            },
+            mint_price: MintPrice {
+                og: U128(0),
+                public: U128(0),
+            },
+            // ContractV1 has no version field
+            state_version: 1,
+            staged_code_hash: None,
+            approved_code_hash: None,
+            roles: LookupMap::new(StorageKey::Roles),
+            pending_owner_id: None,
+            pause_state: PauseState::default(),
+            og_merkle_root: None,
+            og_merkle_claimed: LookupMap::new(StorageKey::OgMerkleClaimed),
+            sale_phases: vec![],
+            phase_minted: LookupMap::new(StorageKey::PhaseMinted),
+            minted_count: LookupMap::new(StorageKey::MintedCount),
+            max_mint_per_account: Some(1),
+            draw_commitments: LookupMap::new(StorageKey::DrawCommitments),
+            raffle_implicit_len: raffle_len,
+            raffle_next_value: raffle_len,
+            raffle_series_by_value: LookupMap::new(StorageKey::RaffleSeriesByValue),
+            raffle_value_by_series: LookupMap::new(StorageKey::RaffleValueBySeries),
+            raffle_mode: RaffleMode::Uniform,
+            raffle_weights: LookupMap::new(StorageKey::RaffleWeights),
+            placeholder_metadata: LazyOption::new(StorageKey::PlaceholderMetadata, None),
+            revealed: true,
+            provenance_hash: None,
+            token_metadata_overrides: LookupMap::new(StorageKey::TokenMetadataOverrides),
+            edition_title: false,
+            events: EventBuffer::default(),
+        }
+    }
+
+    fn from_v2(prev: ContractV2, max_supply_raffle: u32) -> Self {
+        let mut account_id_og = UnorderedMap::new(StorageKey::AccountIdOg);
+        for (account_id, balance) in prev.account_id_og.iter() {
+            account_id_og.insert(account_id, balance);
+        }
+        // V2 values are series "value + 1" out of the pool the contract started with,
+        // draws only ever shrank it
+        let raffle_implicit_len = max_supply_raffle as u64;
+        assert!(
+            raffle_implicit_len >= prev.raffle.len,
+            "Paras: max_supply_raffle is below the raffle length"
+        );
+
+        Self {
+            tokens: prev.tokens,
+            metadata: prev.metadata,
+            token_series_by_id: prev.token_series_by_id,
+            seller_by_id: prev.seller_by_id,
+            raffle: Raffle::from_v1(prev.raffle),
+            token_series_id_minted: prev.token_series_id_minted,
+            treasury_id: prev.treasury_id,
+            whitelist_contract_id: Some(prev.whitelist_contract_id).filter(|id| !id.is_empty()),
+            transaction_fee: prev.transaction_fee,
+            account_id_og,
+            balance_mint_og: prev.balance_mint_og,
+            market_data_transaction_fee: prev.market_data_transaction_fee,
+            mint_price: MintPrice {
+                og: U128(0),
+                public: U128(0),
+            },
+            // ContractV2 has no version field
+            state_version: 2,
+            staged_code_hash: None,
+            approved_code_hash: None,
+            roles: LookupMap::new(StorageKey::Roles),
+            pending_owner_id: None,
+            pause_state: PauseState::default(),
+            og_merkle_root: None,
+            og_merkle_claimed: LookupMap::new(StorageKey::OgMerkleClaimed),
+            sale_phases: vec![],
+            phase_minted: LookupMap::new(StorageKey::PhaseMinted),
+            minted_count: LookupMap::new(StorageKey::MintedCount),
+            max_mint_per_account: Some(1),
+            draw_commitments: LookupMap::new(StorageKey::DrawCommitments),
+            raffle_implicit_len,
+            raffle_next_value: raffle_implicit_len,
+            raffle_series_by_value: LookupMap::new(StorageKey::RaffleSeriesByValue),
+            raffle_value_by_series: LookupMap::new(StorageKey::RaffleValueBySeries),
+            raffle_mode: RaffleMode::Uniform,
+            raffle_weights: LookupMap::new(StorageKey::RaffleWeights),
+            placeholder_metadata: LazyOption::new(StorageKey::PlaceholderMetadata, None),
+            revealed: true,
+            provenance_hash: None,
+            token_metadata_overrides: LookupMap::new(StorageKey::TokenMetadataOverrides),
+            edition_title: false,
+            events: EventBuffer::default(),
         }
     }
 
+    pub fn get_state_version(&self) -> u32 {
+        self.state_version
+    }
+
+    // Upgrade
+
+    /// Stores the wasm passed as raw call input (not JSON) until `upgrade` deploys it
     #[payable]
-    pub fn set_transaction_fee(&mut self, next_fee: u16, start_time: Option<TimestampSec>) {
+    pub fn stage_code(&mut self) -> Base64VecU8 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.tokens.owner_id,
+            "Paras: Owner only"
+        );
+        let code = env::input().expect("Paras: code is required");
+        let initial_storage_usage = env::storage_usage();
+
+        env::storage_write(&StorageKey::StagedCode.try_to_vec().unwrap(), &code);
+        let code_hash = env::sha256(&code);
+        self.staged_code_hash = Some(code_hash.clone());
+
+        NearEvent::paras_admin(ParasAdminEventKind::StageCode(vec![event::StageCodeData {
+            code_hash: Base64VecU8::from(code_hash.clone()),
+            code_len: U64(code.len() as u64),
+        }]))
+        .log();
+
+        // replacing bigger staged code frees storage, nothing to charge then
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            0,
+        );
+
+        code_hash.into()
+    }
+
+    #[payable]
+    pub fn approve_code_hash(&mut self, code_hash: Base64VecU8) {
+        assert_one_yocto();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.tokens.owner_id,
+            "Paras: Owner only"
+        );
+        self.approved_code_hash = Some(code_hash.clone().into());
+
+        NearEvent::paras_admin(ParasAdminEventKind::ApproveCodeHash(vec![
+            event::CodeHashData { code_hash },
+        ]))
+        .log();
+    }
+
+    /// Deploys the staged code on this account and calls `migrate` on it
+    #[payable]
+    pub fn upgrade(&mut self) -> Promise {
         assert_one_yocto();
         assert_eq!(
             env::predecessor_account_id(),
             self.tokens.owner_id,
             "Paras: Owner only"
         );
+        assert!(
+            env::prepaid_gas() > GAS_FOR_UPGRADE + GAS_FOR_MIGRATE,
+            "Paras: not enough gas for upgrade"
+        );
+
+        let staged_code_key = StorageKey::StagedCode.try_to_vec().unwrap();
+        let code = env::storage_read(&staged_code_key).expect("Paras: no staged code");
+        let code_hash = env::sha256(&code);
+        assert_eq!(
+            Some(&code_hash),
+            self.staged_code_hash.as_ref(),
+            "Paras: staged code does not match staged_code_hash"
+        );
+        assert_eq!(
+            Some(&code_hash),
+            self.approved_code_hash.as_ref(),
+            "Paras: staged code is not approved"
+        );
+
+        env::storage_remove(&staged_code_key);
+        self.staged_code_hash = None;
+        self.approved_code_hash = None;
+
+        NearEvent::paras_admin(ParasAdminEventKind::Upgrade(vec![event::CodeHashData {
+            code_hash: Base64VecU8::from(code_hash),
+        }]))
+        .log();
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                b"migrate".to_vec(),
+                b"{}".to_vec(),
+                NO_DEPOSIT,
+                env::prepaid_gas() - GAS_FOR_UPGRADE,
+            )
+    }
+
+    pub fn get_staged_code_hash(&self) -> Option<Base64VecU8> {
+        self.staged_code_hash.clone().map(Base64VecU8::from)
+    }
+
+    pub fn get_approved_code_hash(&self) -> Option<Base64VecU8> {
+        self.approved_code_hash.clone().map(Base64VecU8::from)
+    }
+
+    #[payable]
+    pub fn set_transaction_fee(&mut self, next_fee: u16, start_time: Option<TimestampSec>) {
+        assert_one_yocto();
+        self.assert_role(Role::FeeManager);
 
         assert!(
             next_fee < 10_000,
             "Paras: transaction fee is more than 10_000"
         );
 
+        NearEvent::paras_admin(ParasAdminEventKind::SetTransactionFee(vec![
+            event::SetTransactionFeeData {
+                next_fee,
+                start_time,
+            },
+        ]))
+        .log();
+
         if start_time.is_none() {
             self.transaction_fee.current_fee = next_fee;
             self.transaction_fee.next_fee = None;
@@ -313,719 +731,984 @@ impl Contract {
         return self.raffle.len();
     }
 
-    pub fn get_balance_mint_og(&self) -> u32 {
-        self.balance_mint_og
-    }
+    /// Undrawn series left in the raffle, in pool order
+    pub fn get_raffle_series(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<TokenSeriesId> {
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        assert!(
+            (self.raffle.len() as u128) > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
 
-    pub fn is_og(&self, account_id: &AccountId) -> bool {
-        let contain = self.account_id_og.contains_key(account_id);
-        return contain;
-        // if contain {
-        //     let balance = self.account_id_og.get(account_id).unwrap().clone();
-        //     if balance > 0 {
-        //         true
-        //     } else {
-        //         false
-        //     }
-        // } else {
-        //     false
-        // }
+        (start_index as u64..self.raffle.len())
+            .take(limit)
+            .map(|index| self.internal_raffle_series(self.raffle.get(index).unwrap()))
+            .collect()
     }
 
-    pub fn get_og_balance(&self, account_id: &AccountId) -> u32 {
-        let contain = self.account_id_og.contains_key(account_id);
+    /// Puts series (back) in the raffle pool, e.g. ones created after `new`
+    #[payable]
+    pub fn raffle_add_series(&mut self, token_series_ids: Vec<TokenSeriesId>) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_role(Role::Admin);
 
-        if contain {
-            self.account_id_og.get(account_id).unwrap().clone()
-        } else {
-            0
+        for token_series_id in token_series_ids.iter() {
+            let token_series = self
+                .token_series_by_id
+                .get(token_series_id)
+                .expect("Paras: Token series not exist");
+            assert!(
+                token_series.price.is_none(),
+                "Paras: token series {} has a price, raffle series are sold by the draws",
+                token_series_id
+            );
+            let value = match self.internal_raffle_value(token_series_id) {
+                Some(value) => {
+                    assert!(
+                        self.raffle.position(value).is_none(),
+                        "Paras: token series {} is already in the raffle",
+                        token_series_id
+                    );
+                    value
+                }
+                None => {
+                    let value = self.raffle_next_value;
+                    self.raffle_next_value += 1;
+                    self.raffle_series_by_value.insert(&value, token_series_id);
+                    self.raffle_value_by_series.insert(token_series_id, &value);
+                    value
+                }
+            };
+            self.raffle.push(value);
         }
+        if self.raffle_mode == RaffleMode::Weighted {
+            self.assert_weighted_pool_len();
+        }
+
+        NearEvent::paras_admin(ParasAdminEventKind::RaffleAddSeries(vec![
+            event::RaffleSeriesData {
+                token_series_ids,
+                raffle_length: U64(self.raffle.len()),
+            },
+        ]))
+        .log();
+
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
     }
 
+    /// Takes series out of the raffle pool so they can be kept as reserves
     #[payable]
-    pub fn set_balance_mint_og(&mut self, balance_mint_og: u32) {
+    pub fn raffle_reserve_series(&mut self, token_series_ids: Vec<TokenSeriesId>) {
         assert_one_yocto();
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.tokens.owner_id,
-            "Paras: Owner only"
-        );
-        self.balance_mint_og = balance_mint_og;
+        self.assert_role(Role::Admin);
+
+        for token_series_id in token_series_ids.iter() {
+            let index = self
+                .internal_raffle_value(token_series_id)
+                .and_then(|value| self.raffle.position(value))
+                .expect("Paras: token series is not in the raffle");
+            self.raffle.swap_remove(index);
+        }
+
+        NearEvent::paras_admin(ParasAdminEventKind::RaffleReserveSeries(vec![
+            event::RaffleSeriesData {
+                token_series_ids,
+                raffle_length: U64(self.raffle.len()),
+            },
+        ]))
+        .log();
     }
 
-    pub fn get_og_account_id(&self) -> HashMap<AccountId, u32> {
-        return self.account_id_og.clone();
+    /// Records where the pool values migrated from ContractV2 sit, `limit` indexes per call.
+    /// Adding and reserving series fail until it returns 0.
+    #[payable]
+    pub fn raffle_index_positions(&mut self, limit: u64) -> U64 {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_role(Role::Admin);
+        let indexes_left = self.raffle.index_positions(limit);
+
+        NearEvent::paras_admin(ParasAdminEventKind::RaffleIndexPositions(vec![
+            event::RaffleIndexPositionsData {
+                indexes_left: U64(indexes_left),
+            },
+        ]))
+        .log();
+
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+        U64(indexes_left)
     }
 
     #[payable]
-    pub fn add_og_account_id(&mut self, account_id: AccountId, balance_mint_og: Option<u32>) {
+    pub fn set_raffle_mode(&mut self, raffle_mode: RaffleMode) {
         assert_one_yocto();
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.tokens.owner_id,
-            "Paras: Owner only"
-        );
-        let balance = if let Some(balance) = balance_mint_og {
-            balance
-        } else {
-            self.balance_mint_og
-        };
+        self.assert_role(Role::Admin);
+        if raffle_mode == RaffleMode::Weighted {
+            self.assert_weighted_pool_len();
+        }
+        self.raffle_mode = raffle_mode;
 
-        self.account_id_og.insert(account_id, balance);
+        NearEvent::paras_admin(ParasAdminEventKind::SetRaffleMode(vec![
+            event::SetRaffleModeData { raffle_mode },
+        ]))
+        .log();
     }
 
-    #[payable]
-    pub fn decress_balance_og(&mut self, account_id: AccountId, balance_mint_og: u32) {
-        self.account_id_og.insert(account_id, balance_mint_og - 1);
+    pub fn get_raffle_mode(&self) -> RaffleMode {
+        self.raffle_mode
     }
 
-    #[payable]
-    pub fn remove_og_account_id(&mut self, account_id: AccountId) {
-        assert_one_yocto();
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.tokens.owner_id,
-            "Paras: Owner only"
+    fn assert_weighted_pool_len(&self) {
+        assert!(
+            self.raffle.len() <= MAX_WEIGHTED_POOL_LEN,
+            "Paras: weighted mode supports at most {} series in the raffle",
+            MAX_WEIGHTED_POOL_LEN
         );
-        self.account_id_og.remove(&account_id);
     }
 
-    // Treasury
+    /// Sets weights used in weighted mode, `None` goes back to the remaining copies
     #[payable]
-    pub fn set_treasury(&mut self, treasury_id: ValidAccountId) {
-        assert_one_yocto();
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.tokens.owner_id,
-            "Paras: Owner only"
-        );
-        self.treasury_id = treasury_id.to_string();
+    pub fn set_raffle_weights(&mut self, weights: HashMap<TokenSeriesId, Option<u32>>) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_role(Role::Admin);
+
+        for (token_series_id, weight) in weights.iter() {
+            match weight {
+                Some(weight) => self.raffle_weights.insert(token_series_id, weight),
+                None => self.raffle_weights.remove(token_series_id),
+            };
+        }
+
+        NearEvent::paras_admin(ParasAdminEventKind::SetRaffleWeights(vec![
+            event::SetRaffleWeightsData { weights },
+        ]))
+        .log();
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        refund_deposit(storage_used, 0);
     }
 
-    // CUSTOM
+    /// Weight the series is drawn with in weighted mode, 0 once it is minted out
+    pub fn get_raffle_weight(&self, token_series_id: TokenSeriesId) -> U64 {
+        U64(self.internal_raffle_weight(&token_series_id))
+    }
 
-    #[payable]
-    pub fn nft_create_series(
-        &mut self,
-        creator_id: Option<ValidAccountId>,
-        token_metadata: TokenMetadata,
-        price: Option<U128>,
-        royalty: Option<HashMap<AccountId, u32>>,
-    ) -> TokenSeriesJson {
-        let initial_storage_usage = env::storage_usage();
-        let caller_id = env::predecessor_account_id();
+    fn internal_raffle_series(&self, value: u64) -> TokenSeriesId {
+        self.raffle_series_by_value
+            .get(&value)
+            .unwrap_or_else(|| (value + 1).to_string())
+    }
 
-        assert_eq!(caller_id, self.tokens.owner_id, "Paras: Only owner");
+    /// `None` for series without a copies limit
+    fn internal_copies_left(&self, token_series_id: &TokenSeriesId) -> Option<u64> {
+        match self.token_series_by_id.get(token_series_id) {
+            Some(token_series) if token_series.is_mintable => token_series
+                .metadata
+                .copies
+                .map(|copies| copies.saturating_sub(token_series.tokens.len())),
+            _ => Some(0),
+        }
+    }
 
-        if creator_id.is_some() {
-            assert_eq!(
-                creator_id.unwrap().to_string(),
-                caller_id,
-                "Paras: Caller is not creator_id"
-            );
+    /// Takes a series minted out outside the draws out of the weighted pool. A pool that
+    /// isn't indexed yet keeps it, with weight 0 it is never drawn.
+    fn internal_raffle_remove_minted_out(&mut self, token_series_id: &TokenSeriesId) {
+        if self.raffle_mode != RaffleMode::Weighted || !self.raffle.is_indexed() {
+            return;
+        }
+        if let Some(index) = self
+            .internal_raffle_value(token_series_id)
+            .and_then(|value| self.raffle.position(value))
+        {
+            self.raffle.swap_remove(index);
         }
+    }
 
-        let token_series_id = format!("{}", (self.token_series_by_id.len() + 1));
+    /// Weight of every pool entry, in pool order
+    fn internal_raffle_weights(&self) -> Vec<u64> {
+        (0..self.raffle.len())
+            .map(|index| {
+                let value = self.raffle.get(index).unwrap();
+                self.internal_raffle_weight(&self.internal_raffle_series(value))
+            })
+            .collect()
+    }
+
+    fn internal_raffle_weight(&self, token_series_id: &TokenSeriesId) -> u64 {
+        match self.internal_copies_left(token_series_id) {
+            Some(0) => 0,
+            copies_left => self
+                .raffle_weights
+                .get(token_series_id)
+                .map(u64::from)
+                .unwrap_or_else(|| copies_left.unwrap_or(1)),
+        }
+    }
+
+    /// Raffle value standing for `token_series_id`, if it ever got one
+    fn internal_raffle_value(&self, token_series_id: &TokenSeriesId) -> Option<u64> {
+        if let Some(value) = self.raffle_value_by_series.get(token_series_id) {
+            return Some(value);
+        }
+        let series_number: u64 = token_series_id.parse().ok()?;
+        if series_number > 0 && series_number <= self.raffle_implicit_len {
+            Some(series_number - 1)
+        } else {
+            None
+        }
+    }
 
+    /// Series that ever joined the raffle are sold by the draws under the sale phases,
+    /// so they can't carry a price of their own and go through `nft_buy`
+    fn assert_not_raffle_series(&self, token_series_id: &TokenSeriesId) {
         assert!(
-            self.token_series_by_id.get(&token_series_id).is_none(),
-            "Paras: duplicate token_series_id"
+            self.internal_raffle_value(token_series_id).is_none(),
+            "Paras: token series {} is sold through the raffle",
+            token_series_id
         );
+    }
 
-        let title = token_metadata.title.clone();
-        assert!(title.is_some(), "Paras: token_metadata.title is required");
+    pub fn get_balance_mint_og(&self) -> u32 {
+        self.balance_mint_og
+    }
 
-        let mut total_perpetual = 0;
-        let mut total_accounts = 0;
-        let royalty_res: HashMap<AccountId, u32> = if let Some(royalty) = royalty {
-            for (k, v) in royalty.iter() {
-                if !is_valid_account_id(k.as_bytes()) {
-                    env::panic("Not valid account_id for royalty".as_bytes());
-                };
-                total_perpetual += *v;
-                total_accounts += 1;
-            }
-            royalty 
-        } else {
-            HashMap::new()
-        };
+    pub fn is_og(&self, account_id: &AccountId) -> bool {
+        self.account_id_og.get(account_id).is_some()
+    }
 
-        assert!(total_accounts <= 10, "Paras: royalty exceeds 10 accounts");
+    pub fn get_og_balance(&self, account_id: &AccountId) -> u32 {
+        self.account_id_og.get(account_id).unwrap_or(0)
+    }
+
+    #[payable]
+    pub fn set_balance_mint_og(&mut self, balance_mint_og: u32) {
+        assert_one_yocto();
+        self.assert_role(Role::AllowlistManager);
+        self.balance_mint_og = balance_mint_og;
+
+        NearEvent::paras_admin(ParasAdminEventKind::SetBalanceMintOg(vec![
+            event::SetBalanceMintOgData { balance_mint_og },
+        ]))
+        .log();
+    }
 
+    #[payable]
+    pub fn set_mint_price(&mut self, price_og: U128, price_public: U128) {
+        assert_one_yocto();
+        self.assert_role(Role::FeeManager);
         assert!(
-            total_perpetual <= 9000,
-            "Paras Exceeds maximum royalty -> 9000",
+            price_og.0 < MAX_PRICE && price_public.0 < MAX_PRICE,
+            "Paras: price higher than {}",
+            MAX_PRICE
         );
-
-        let price_res: Option<u128> = if price.is_some() {
-            assert!(
-                price.unwrap().0 < MAX_PRICE,
-                "Paras: price higher than {}",
-                MAX_PRICE
-            );
-            Some(price.unwrap().0)
-        } else {
-            None
+        self.mint_price = MintPrice {
+            og: price_og,
+            public: price_public,
         };
 
-        self.token_series_by_id.insert(
-            &token_series_id,
-            &TokenSeries {
-                metadata: token_metadata.clone(),
-                creator_id: caller_id.to_string(),
-                tokens: UnorderedSet::new(
-                    StorageKey::TokensBySeriesInner {
-                        token_series: token_series_id.clone(),
-                    }
-                    .try_to_vec()
-                    .unwrap(),
-                ),
-                price: price_res,
-                is_mintable: true,
-                royalty: royalty_res.clone(),
+        NearEvent::paras_admin(ParasAdminEventKind::SetMintPrice(vec![
+            event::SetMintPriceData {
+                price_og,
+                price_public,
             },
-        );
-
-        // set market data transaction fee
-        let current_transaction_fee = self.calculate_current_transaction_fee();
-        self.market_data_transaction_fee
-            .transaction_fee
-            .insert(&token_series_id, &current_transaction_fee);
-
-        env::log(
-            json!({
-                "type": "nft_create_series",
-                "params": {
-                    "token_series_id": token_series_id,
-                    "token_metadata": token_metadata,
-                    "creator_id": caller_id,
-                    "price": price,
-                    "royalty": royalty_res,
-                    "transaction_fee": &current_transaction_fee.to_string()
-                }
-            })
-            .to_string()
-            .as_bytes(),
-        );
+        ]))
+        .log();
+    }
 
-        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+    pub fn get_mint_price(&self) -> &MintPrice {
+        &self.mint_price
+    }
 
-        TokenSeriesJson {
-            token_series_id,
-            metadata: token_metadata,
-            creator_id: caller_id.into(),
-            royalty: royalty_res,
-            transaction_fee: Some(current_transaction_fee.into()),
+    /// Price per token a draw charges `account_id`, the same one as `internal_mint_terms`:
+    /// the active phase price, or between phases the next one's, `None` once the last phase
+    /// ended. Without phases it is the OG price for OG accounts and valid merkle proofs.
+    pub fn get_mint_price_for_account(
+        &self,
+        account_id: AccountId,
+        merkle_proof: Option<MerkleProof>,
+    ) -> Option<U128> {
+        if !self.sale_phases.is_empty() {
+            return phase::current_or_next_phase(&self.sale_phases, to_sec(env::block_timestamp()))
+                .map(|phase| phase.price);
+        }
+        match self.internal_og_mints_left(&account_id, merkle_proof.as_ref()) {
+            Some(_) => Some(self.mint_price.og),
+            None => Some(self.mint_price.public),
         }
     }
 
-    #[payable]
-    pub fn nft_create_series_custom(
-        &mut self,
-        token_series_id: TokenSeriesId,
-        creator_id: Option<ValidAccountId>,
-        token_metadata: TokenMetadata,
-        price: Option<U128>,
-        royalty: Option<HashMap<AccountId, u32>>,
-    ) -> TokenSeriesJson {
-        let initial_storage_usage = env::storage_usage();
-        let caller_id = env::predecessor_account_id();
-
-        assert_eq!(caller_id, self.tokens.owner_id, "Paras: Only owner");
-
-        if creator_id.is_some() {
-            assert_eq!(
-                creator_id.unwrap().to_string(),
-                caller_id,
-                "Paras: Caller is not creator_id"
-            );
-        }
+    pub fn get_og_account_count(&self) -> U64 {
+        self.account_id_og.len().into()
+    }
 
+    pub fn get_og_accounts(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<OgAccountJson> {
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
         assert!(
-            self.token_series_by_id.get(&token_series_id).is_none(),
-            "Paras: duplicate token_series_id"
+            (self.account_id_og.len() as u128) > start_index,
+            "Out of bounds, please use a smaller from_index."
         );
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
 
-        let title = token_metadata.title.clone();
-        assert!(title.is_some(), "Paras: token_metadata.title is required");
+        self.account_id_og
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .map(|(account_id, balance_mint_og)| OgAccountJson {
+                account_id,
+                balance_mint_og,
+            })
+            .collect()
+    }
 
-        let mut total_perpetual = 0;
-        let mut total_accounts = 0;
-        let royalty_res: HashMap<AccountId, u32> = if let Some(royalty) = royalty {
-            for (k, v) in royalty.iter() {
-                if !is_valid_account_id(k.as_bytes()) {
-                    env::panic("Not valid account_id for royalty".as_bytes());
-                };
-                total_perpetual += *v;
-                total_accounts += 1;
-            }
-            royalty
+    #[payable]
+    pub fn add_og_account_id(&mut self, account_id: AccountId, balance_mint_og: Option<u32>) {
+        assert_one_yocto();
+        self.assert_role(Role::AllowlistManager);
+        let balance = if let Some(balance) = balance_mint_og {
+            balance
         } else {
-            HashMap::new()
+            self.balance_mint_og
         };
 
-        assert!(total_accounts <= 10, "Paras: royalty exceeds 10 accounts");
+        self.account_id_og.insert(&account_id, &balance);
 
-        assert!(
-            total_perpetual <= 9000,
-            "Paras Exceeds maximum royalty -> 9000",
-        );
+        NearEvent::paras_admin(ParasAdminEventKind::AddOgAccountIds(vec![
+            event::AddOgAccountIdsData {
+                accounts: vec![(account_id, balance)].into_iter().collect(),
+            },
+        ]))
+        .log();
+    }
 
-        let price_res: Option<u128> = if price.is_some() {
-            assert!(
-                price.unwrap().0 < MAX_PRICE,
-                "Paras: price higher than {}",
-                MAX_PRICE
-            );
-            Some(price.unwrap().0)
-        } else {
-            None
-        };
+    /// Bulk version of `add_og_account_id`, the caller pays for the storage
+    #[payable]
+    pub fn add_og_account_ids(&mut self, accounts: HashMap<AccountId, u32>) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_role(Role::AllowlistManager);
 
-        self.token_series_by_id.insert(
-            &token_series_id,
-            &TokenSeries {
-                metadata: token_metadata.clone(),
-                creator_id: caller_id.to_string(),
-                tokens: UnorderedSet::new(
-                    StorageKey::TokensBySeriesInner {
-                        token_series: token_series_id.clone(),
-                    }
-                    .try_to_vec()
-                    .unwrap(),
-                ),
-                price: price_res,
-                is_mintable: true,
-                royalty: royalty_res.clone(),
-            },
-        );
+        for (account_id, balance) in accounts.iter() {
+            if !is_valid_account_id(account_id.as_bytes()) {
+                env::panic("Not valid account_id for OG".as_bytes());
+            };
+            self.account_id_og.insert(account_id, balance);
+        }
 
-        // set market data transaction fee
-        let current_transaction_fee = self.calculate_current_transaction_fee();
-        self.market_data_transaction_fee
-            .transaction_fee
-            .insert(&token_series_id, &current_transaction_fee);
+        NearEvent::paras_admin(ParasAdminEventKind::AddOgAccountIds(vec![
+            event::AddOgAccountIdsData { accounts },
+        ]))
+        .log();
 
-        env::log(
-            json!({
-                "type": "nft_create_series",
-                "params": {
-                    "token_series_id": token_series_id,
-                    "token_metadata": token_metadata,
-                    "creator_id": caller_id,
-                    "price": price,
-                    "royalty": royalty_res,
-                    "transaction_fee": &current_transaction_fee.to_string()
-                }
-            })
-            .to_string()
-            .as_bytes(),
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            0,
         );
+    }
 
-        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+    #[payable]
+    pub fn remove_og_account_id(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::AllowlistManager);
+        self.account_id_og.remove(&account_id);
 
-        TokenSeriesJson {
-            token_series_id,
-            metadata: token_metadata,
-            creator_id: caller_id.into(),
-            royalty: royalty_res,
-            transaction_fee: Some(current_transaction_fee.into()),
-        }
+        NearEvent::paras_admin(ParasAdminEventKind::RemoveOgAccountIds(vec![
+            event::RemoveOgAccountIdsData {
+                account_ids: vec![account_id],
+            },
+        ]))
+        .log();
     }
 
     #[payable]
-    pub fn nft_mint_creator(
-        &mut self,
-        token_series_id: TokenSeriesId,
-        receiver_id: ValidAccountId,
-    ) -> TokenId {
-        let initial_storage_usage = env::storage_usage();
+    pub fn remove_og_account_ids(&mut self, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_role(Role::AllowlistManager);
+        for account_id in account_ids.iter() {
+            self.account_id_og.remove(account_id);
+        }
 
-        let token_series = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .expect("Paras: Token series not exist");
-        assert_eq!(
-            env::predecessor_account_id(),
-            token_series.creator_id,
-            "Paras: not creator"
-        );
-        let token_id: TokenId = self._nft_mint_series(token_series_id, receiver_id.to_string());
+        NearEvent::paras_admin(ParasAdminEventKind::RemoveOgAccountIds(vec![
+            event::RemoveOgAccountIdsData { account_ids },
+        ]))
+        .log();
+    }
 
-        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+    fn internal_decrease_og_balance(&mut self, account_id: &AccountId, quantity: u32) {
+        let balance = self.get_og_balance(account_id);
+        assert!(balance >= quantity, "Not enough balance in OG");
+        self.account_id_og.insert(account_id, &(balance - quantity));
+    }
 
-        NearEvent::log_nft_mint(receiver_id.to_string(), vec![token_id.clone()], None);
+    /// OG accounts that are not in `account_id_og` prove their allowance against this root
+    #[payable]
+    pub fn set_og_merkle_root(&mut self, merkle_root: Option<Base64VecU8>) {
+        assert_one_yocto();
+        self.assert_role(Role::AllowlistManager);
+        self.og_merkle_root = merkle_root.clone().map(|root| root.into());
 
-        token_id
+        NearEvent::paras_admin(ParasAdminEventKind::SetOgMerkleRoot(vec![
+            event::SetOgMerkleRootData { merkle_root },
+        ]))
+        .log();
     }
 
-    //draw a token from a token series
-    #[payable]
-    pub fn draw_and_mint(&mut self, receiver_id: ValidAccountId) -> TokenId {
-        let initial_storage_usage = env::storage_usage();
-        let caller = env::predecessor_account_id();
-        // log(token_series_id.as_bytes());
+    pub fn get_og_merkle_root(&self) -> Option<Base64VecU8> {
+        self.og_merkle_root.clone().map(Base64VecU8::from)
+    }
 
-        let account_id: ValidAccountId = caller.clone().try_into().unwrap();
+    pub fn get_og_merkle_claimed(&self, account_id: AccountId) -> u32 {
+        self.og_merkle_claimed.get(&account_id).unwrap_or(0)
+    }
 
-        if self.get_raffle_length() == 0 {
-            panic!("Sold Out")
+    /// OG mints left for `account_id`, or None when it is not an OG.
+    /// The on-chain OG list wins over a merkle proof for the same account.
+    fn internal_og_mints_left(
+        &self,
+        account_id: &AccountId,
+        merkle_proof: Option<&MerkleProof>,
+    ) -> Option<u32> {
+        if let Some(balance) = self.account_id_og.get(account_id) {
+            return Some(balance);
         }
 
-        if !self.is_og(&caller) {
-            let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().expect(
-                "Could not find tokens_per_owner when calling a method on the enumeration standard.",
-            );
+        let merkle_proof = merkle_proof?;
+        let root = self
+            .og_merkle_root
+            .as_ref()
+            .expect("Paras: merkle root is not set");
+        let leaf = merkle::leaf_hash(account_id, merkle_proof.allowance);
+        assert!(
+            merkle::verify(root, leaf, &merkle_proof.proof),
+            "Paras: invalid merkle proof"
+        );
 
-            let token_set = tokens_per_owner.get(account_id.as_ref());
-            if !token_set.is_none() {
-                let token_tmp = token_set.unwrap();
-                if token_tmp.len() >= 1 {
-                    panic!("Mint Not Allowed")
-                }
-            }
+        let claimed = self.get_og_merkle_claimed(account_id.clone());
+        Some(merkle_proof.allowance.saturating_sub(claimed))
+    }
+
+    fn internal_use_og_mint(&mut self, account_id: &AccountId, quantity: u32) {
+        if self.is_og(account_id) {
+            self.internal_decrease_og_balance(account_id, quantity);
         } else {
-            let balance = self.account_id_og.get(&caller).unwrap().clone();
-            if balance < 1 {
-                panic!("Mint Not Allowed")
-            }
+            let claimed = self.get_og_merkle_claimed(account_id.clone());
+            self.og_merkle_claimed
+                .insert(account_id, &(claimed + quantity));
         }
+    }
 
-        let token_series_id = (self.raffle.draw() + 1).to_string(); //random token series id from 1 to max size
-
-        self.token_series_by_id
-            .get(&token_series_id)
-            .expect("Paras: Token series not exist");
-        // let token_series = self.token_series_by_id.get(&token_series_id).expect("Paras: Token series not exist");
-        // assert_eq!(env::predecessor_account_id(), token_series.creator_id, "Paras: not creator");
-        let token_id: TokenId = self._nft_mint_series(token_series_id, receiver_id.to_string());
+    // Sale phases
 
-        if self.is_og(&caller) {
-            let balance = self.get_og_balance(&caller);
-            self.decress_balance_og(caller.clone(), balance);
-        }
+    /// Replaces the whole schedule, an empty one goes back to the OG / public rules
+    #[payable]
+    pub fn set_sale_phases(&mut self, sale_phases: Vec<SalePhase>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        phase::assert_valid_schedule(&sale_phases, MAX_PRICE);
+        self.sale_phases = sale_phases.clone();
+
+        NearEvent::paras_admin(ParasAdminEventKind::SetSalePhases(vec![
+            event::SetSalePhasesData { sale_phases },
+        ]))
+        .log();
+    }
 
-        // ext_whitelist_contract::incress_balance_whitelist(
-        //     caller.clone(),
-        //     &self.whitelist_contract_id.clone(),
-        //     NO_DEPOSIT,
-        //     GAS_FOR_RESOLVE_TRANSFER,
-        // );
+    pub fn get_sale_phases(&self) -> Vec<SalePhase> {
+        self.sale_phases.clone()
+    }
 
-        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+    pub fn get_current_phase(&self) -> Option<SalePhase> {
+        phase::current_phase(&self.sale_phases, to_sec(env::block_timestamp())).cloned()
+    }
 
-        NearEvent::log_nft_mint(receiver_id.to_string(), vec![token_id.clone()], None);
+    pub fn get_phase_minted(&self, phase_id: String, account_id: AccountId) -> u32 {
+        self.phase_minted.get(&(phase_id, account_id)).unwrap_or(0)
+    }
 
-        token_id
+    fn internal_active_phase(&self) -> &SalePhase {
+        phase::current_phase(&self.sale_phases, to_sec(env::block_timestamp()))
+            .expect("Paras: no active sale phase")
     }
 
-    //custom mint token series
-    #[payable]
-    pub fn nft_mint(
-        &mut self,
-        token_series_id: TokenSeriesId,
-        receiver_id: ValidAccountId,
-    ) -> TokenId {
-        let caller = env::predecessor_account_id();
-        let initial_storage_usage = env::storage_usage();
-        // log(token_series_id.as_bytes());
-        let contain = self.account_id_og.contains_key(&caller);
-        assert!(contain, "Not in OG list");
+    /// Checks that `account_id` may mint right now. `og_only` is for `nft_mint`,
+    /// which picks the series and so always spends an OG mint.
+    fn internal_mint_terms(
+        &self,
+        account_id: &AccountId,
+        merkle_proof: Option<&MerkleProof>,
+        og_only: bool,
+        quantity: u32,
+    ) -> MintTerms {
+        let og_mints_left = self.internal_og_mints_left(account_id, merkle_proof);
+
+        if self.sale_phases.is_empty() {
+            return match og_mints_left {
+                Some(left) if left < quantity && og_only => panic!("Not enough balance in OG"),
+                Some(left) if left < quantity => panic!("Mint Not Allowed"),
+                Some(_) => MintTerms {
+                    price: self.mint_price.og.0,
+                    quantity,
+                    use_og_mint: true,
+                    phase_id: None,
+                },
+                None => {
+                    assert!(!og_only, "Not in OG list");
+                    self.assert_below_max_mint(account_id, quantity);
+                    MintTerms {
+                        price: self.mint_price.public.0,
+                        quantity,
+                        use_og_mint: false,
+                        phase_id: None,
+                    }
+                }
+            };
+        }
 
-        let balance_og = self.account_id_og.get(&caller).unwrap().clone();
-        if balance_og <= 0 {
-            panic!("Not enough balance in OG");
+        let phase = self.internal_active_phase();
+
+        let use_og_mint = match phase.eligibility {
+            PhaseEligibility::Og => {
+                assert!(
+                    self.is_og(account_id),
+                    "Paras: phase {} is for OG accounts only",
+                    phase.phase_id
+                );
+                true
+            }
+            PhaseEligibility::Allowlist => {
+                assert!(
+                    og_mints_left.is_some(),
+                    "Paras: phase {} is for allowlisted accounts only",
+                    phase.phase_id
+                );
+                true
+            }
+            PhaseEligibility::Public => og_only,
+        };
+        if use_og_mint {
+            match og_mints_left {
+                None => panic!("Not in OG list"),
+                Some(left) if left < quantity => panic!("Not enough balance in OG"),
+                Some(_) => {}
+            }
+        } else {
+            self.assert_below_max_mint(account_id, quantity);
         }
 
-        self.token_series_by_id
-            .get(&token_series_id)
-            .expect("Paras: Token series not exist");
-        // let token_series = self.token_series_by_id.get(&token_series_id).expect("Paras: Token series not exist");
-        // assert_eq!(env::predecessor_account_id(), token_series.creator_id, "Paras: not creator");
-        let token_id: TokenId = self._nft_mint_series(token_series_id, receiver_id.to_string());
+        if let Some(limit_per_account) = phase.limit_per_account {
+            let minted = self.get_phase_minted(phase.phase_id.clone(), account_id.clone());
+            assert!(
+                minted + quantity <= limit_per_account,
+                "Paras: phase {} limit of {} per account reached",
+                phase.phase_id,
+                limit_per_account
+            );
+        }
 
-        //decrease balance in OG
-        self.decress_balance_og(caller.clone(), balance_og);
+        MintTerms {
+            price: phase.price.0,
+            quantity,
+            use_og_mint,
+            phase_id: Some(phase.phase_id.clone()),
+        }
+    }
 
-        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+    fn internal_record_mint(&mut self, account_id: &AccountId, terms: &MintTerms) {
+        let minted_count = self.get_minted_count(account_id.clone());
+        self.minted_count
+            .insert(account_id, &(minted_count + terms.quantity));
 
-        NearEvent::log_nft_mint(receiver_id.to_string(), vec![token_id.clone()], None);
+        if terms.use_og_mint {
+            self.internal_use_og_mint(account_id, terms.quantity);
+        }
+        if let Some(phase_id) = &terms.phase_id {
+            let key = (phase_id.clone(), account_id.clone());
+            let minted = self.phase_minted.get(&key).unwrap_or(0);
+            self.phase_minted.insert(&key, &(minted + terms.quantity));
+        }
+    }
 
-        token_id
+    /// Mints that don't spend an OG allowance are capped by everything the account
+    /// minted before, whatever it still holds
+    fn assert_below_max_mint(&self, account_id: &AccountId, quantity: u32) {
+        if let Some(max_mint_per_account) = self.max_mint_per_account {
+            if self.get_minted_count(account_id.clone()) + quantity > max_mint_per_account {
+                panic!("Mint Not Allowed")
+            }
+        }
     }
 
     #[payable]
-    pub fn nft_mint_and_approve(
-        &mut self,
-        token_series_id: TokenSeriesId,
-        account_id: ValidAccountId,
-        msg: Option<String>,
-    ) -> Option<Promise> {
-        let initial_storage_usage = env::storage_usage();
+    pub fn set_max_mint_per_account(&mut self, max_mint_per_account: Option<u32>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.max_mint_per_account = max_mint_per_account;
 
-        let token_series = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .expect("Paras: Token series not exist");
-        assert_eq!(
-            env::predecessor_account_id(),
-            token_series.creator_id,
-            "Paras: not creator"
-        );
-        let token_id: TokenId =
-            self._nft_mint_series(token_series_id, token_series.creator_id.clone());
+        NearEvent::paras_admin(ParasAdminEventKind::SetMaxMintPerAccount(vec![
+            event::SetMaxMintPerAccountData {
+                max_mint_per_account,
+            },
+        ]))
+        .log();
+    }
 
-        // Need to copy the nft_approve code here to solve the gas problem
-        // get contract-level LookupMap of token_id to approvals HashMap
-        let approvals_by_id = self.tokens.approvals_by_id.as_mut().unwrap();
+    pub fn get_max_mint_per_account(&self) -> Option<u32> {
+        self.max_mint_per_account
+    }
 
-        // update HashMap of approvals for this token
-        let approved_account_ids = &mut approvals_by_id
-            .get(&token_id)
-            .unwrap_or_else(|| HashMap::new());
-        let account_id: AccountId = account_id.into();
-        let approval_id: u64 = self
-            .tokens
-            .next_approval_id_by_id
-            .as_ref()
-            .unwrap()
-            .get(&token_id)
-            .unwrap_or_else(|| 1u64);
-        approved_account_ids.insert(account_id.clone(), approval_id);
+    pub fn get_minted_count(&self, account_id: AccountId) -> u32 {
+        self.minted_count.get(&account_id).unwrap_or(0)
+    }
 
-        // save updated approvals HashMap to contract's LookupMap
-        approvals_by_id.insert(&token_id, &approved_account_ids);
+    // Roles
 
-        // increment next_approval_id for this token
-        self.tokens
-            .next_approval_id_by_id
-            .as_mut()
-            .unwrap()
-            .insert(&token_id, &(approval_id + 1));
+    #[payable]
+    pub fn grant_role(&mut self, account_id: ValidAccountId, role: Role) {
+        assert_one_yocto();
+        self.assert_can_manage_role(role);
 
-        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+        let account_id: AccountId = account_id.into();
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        if roles.contains(&role) {
+            return;
+        }
+        roles.push(role);
+        self.roles.insert(&account_id, &roles);
+
+        NearEvent::paras_admin(ParasAdminEventKind::GrantRole(vec![event::RoleData {
+            account_id,
+            role,
+            sender_id: env::predecessor_account_id(),
+        }]))
+        .log();
+    }
 
-        NearEvent::log_nft_mint(
-            token_series.creator_id.clone(),
-            vec![token_id.clone()],
-            None,
-        );
+    #[payable]
+    pub fn revoke_role(&mut self, account_id: ValidAccountId, role: Role) {
+        assert_one_yocto();
+        self.assert_can_manage_role(role);
 
-        if let Some(msg) = msg {
-            Some(ext_approval_receiver::nft_on_approve(
-                token_id,
-                token_series.creator_id,
-                approval_id,
-                msg,
-                &account_id,
-                NO_DEPOSIT,
-                env::prepaid_gas() - GAS_FOR_NFT_APPROVE - GAS_FOR_MINT,
-            ))
+        let account_id: AccountId = account_id.into();
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        if !roles.contains(&role) {
+            return;
+        }
+        roles.retain(|r| *r != role);
+        if roles.is_empty() {
+            self.roles.remove(&account_id);
         } else {
-            None
+            self.roles.insert(&account_id, &roles);
         }
+
+        NearEvent::paras_admin(ParasAdminEventKind::RevokeRole(vec![event::RoleData {
+            account_id,
+            role,
+            sender_id: env::predecessor_account_id(),
+        }]))
+        .log();
     }
 
-    fn _nft_mint_series(
-        &mut self,
-        token_series_id: TokenSeriesId,
-        receiver_id: AccountId,
-    ) -> TokenId {
-        let mut token_series = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .expect("Paras: Token series not exist");
-        assert!(
-            token_series.is_mintable,
-            "Paras: Token series is not mintable"
-        );
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        self.internal_has_role(&account_id, role)
+    }
 
-        let num_tokens = token_series.tokens.len();
-        let max_copies = token_series.metadata.copies.unwrap_or(u64::MAX);
-        assert!(num_tokens < max_copies, "Series supply maxed");
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        self.roles.get(&account_id).unwrap_or_default()
+    }
 
-        if (num_tokens + 1) >= max_copies {
-            token_series.is_mintable = false;
+    fn internal_has_role(&self, account_id: &AccountId, role: Role) -> bool {
+        if *account_id == self.tokens.owner_id {
+            return true;
         }
+        let roles = self.roles.get(account_id).unwrap_or_default();
+        roles.contains(&role) || roles.contains(&Role::Admin)
+    }
 
-        let token_id = format!("{}{}{}", &token_series_id, TOKEN_DELIMETER, num_tokens + 1);
-        token_series.tokens.insert(&token_id);
-        self.token_series_by_id
-            .insert(&token_series_id, &token_series);
-
-        // you can add custom metadata to each token here
-        let metadata = Some(TokenMetadata {
-            title: None,       // ex. "Arch Nemesis: Mail Carrier" or "Parcel #5055"
-            description: None, // free-form description
-            media: None, // URL to associated media, preferably to decentralized, content-addressed storage
-            media_hash: None, // Base64-encoded sha256 hash of content referenced by the `media` field. Required if `media` is included.
-            copies: None, // number of copies of this set of metadata in existence when token was minted.
-            issued_at: Some(env::block_timestamp().to_string()), // ISO 8601 datetime when token was issued or minted
-            expires_at: None,     // ISO 8601 datetime when token expires
-            starts_at: None,      // ISO 8601 datetime when token starts being valid
-            updated_at: None,     // ISO 8601 datetime when token was last updated
-            extra: None, // anything extra the NFT wants to store on-chain. Can be stringified JSON.
-            reference: None, // URL to an off-chain JSON file with more info.
-            reference_hash: None, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
-        });
+    fn assert_role(&self, role: Role) {
+        assert!(
+            self.internal_has_role(&env::predecessor_account_id(), role),
+            "Paras: {:?} role required",
+            role
+        );
+    }
 
-        //let token = self.tokens.mint(token_id, receiver_id, metadata);
-        // From : https://github.com/near/near-sdk-rs/blob/master/near-contract-standards/src/non_fungible_token/core/core_impl.rs#L359
-        // This allows lazy minting
+    /// Admins manage every role except their own, which only the owner hands out
+    fn assert_can_manage_role(&self, role: Role) {
+        if role == Role::Admin {
+            assert_eq!(
+                env::predecessor_account_id(),
+                self.tokens.owner_id,
+                "Paras: Owner only"
+            );
+        } else {
+            self.assert_role(Role::Admin);
+        }
+    }
 
-        let owner_id: AccountId = receiver_id;
-        self.tokens.owner_by_id.insert(&token_id, &owner_id);
+    // Pause
 
-        self.tokens
-            .token_metadata_by_id
-            .as_mut()
-            .and_then(|by_id| by_id.insert(&token_id, &metadata.as_ref().unwrap()));
+    #[payable]
+    pub fn set_pause_state(
+        &mut self,
+        minting: Option<bool>,
+        transfers: Option<bool>,
+        payouts: Option<bool>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::Pauser);
 
-        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
-            let mut token_ids = tokens_per_owner.get(&owner_id).unwrap_or_else(|| {
-                UnorderedSet::new(StorageKey::TokensPerOwner {
-                    account_hash: env::sha256(&owner_id.as_bytes()),
-                })
-            });
-            token_ids.insert(&token_id);
-            tokens_per_owner.insert(&owner_id, &token_ids);
+        if let Some(minting) = minting {
+            self.pause_state.minting = minting;
+        }
+        if let Some(transfers) = transfers {
+            self.pause_state.transfers = transfers;
+        }
+        if let Some(payouts) = payouts {
+            self.pause_state.payouts = payouts;
         }
 
-        self.token_series_id_minted = self.token_series_id_minted + 1;
-
-        //cross contract call to update balance whitelist
+        NearEvent::paras_admin(ParasAdminEventKind::SetPauseState(vec![
+            event::SetPauseStateData {
+                pause_state: self.pause_state.clone(),
+                sender_id: env::predecessor_account_id(),
+            },
+        ]))
+        .log();
+    }
 
-        token_id
+    pub fn get_pause_state(&self) -> &PauseState {
+        &self.pause_state
     }
 
-    #[payable]
-    pub fn nft_set_series_non_mintable(&mut self, token_series_id: TokenSeriesId) {
-        assert_one_yocto();
+    fn assert_minting_not_paused(&self) {
+        assert!(!self.pause_state.minting, "Paras: minting is paused");
+    }
 
-        let mut token_series = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .expect("Token series not exist");
-        assert_eq!(
-            env::predecessor_account_id(),
-            token_series.creator_id,
-            "Paras: Creator only"
-        );
+    fn assert_transfers_not_paused(&self) {
+        assert!(!self.pause_state.transfers, "Paras: transfers are paused");
+    }
 
-        assert_eq!(
-            token_series.is_mintable, true,
-            "Paras: already non-mintable"
-        );
+    fn assert_payouts_not_paused(&self) {
+        assert!(!self.pause_state.payouts, "Paras: payouts are paused");
+    }
 
-        assert_eq!(
-            token_series.metadata.copies, None,
-            "Paras: decrease supply if copies not null"
-        );
+    // Treasury
+    #[payable]
+    pub fn set_treasury(&mut self, treasury_id: ValidAccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.treasury_id = treasury_id.to_string();
 
-        token_series.is_mintable = false;
-        self.token_series_by_id
-            .insert(&token_series_id, &token_series);
-        env::log(
-            json!({
-                "type": "nft_set_series_non_mintable",
-                "params": {
-                    "token_series_id": token_series_id,
-                }
-            })
-            .to_string()
-            .as_bytes(),
-        );
+        NearEvent::paras_admin(ParasAdminEventKind::SetTreasury(vec![
+            event::SetTreasuryData {
+                treasury_id: self.treasury_id.clone(),
+            },
+        ]))
+        .log();
     }
 
+    // CUSTOM
+
     #[payable]
-    pub fn nft_decrease_series_copies(
+    pub fn nft_create_series(
         &mut self,
-        token_series_id: TokenSeriesId,
-        decrease_copies: U64,
-    ) -> U64 {
-        assert_one_yocto();
-
-        let mut token_series = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .expect("Token series not exist");
-        assert_eq!(
-            env::predecessor_account_id(),
-            token_series.creator_id,
-            "Paras: Creator only"
-        );
+        creator_id: Option<ValidAccountId>,
+        token_metadata: TokenMetadata,
+        price: Option<U128>,
+        royalty: Option<HashMap<AccountId, u32>>,
+    ) -> TokenSeriesJson {
+        let initial_storage_usage = env::storage_usage();
+        let caller_id = env::predecessor_account_id();
 
-        let minted_copies = token_series.tokens.len();
-        let copies = token_series.metadata.copies.unwrap();
+        self.assert_role(Role::Minter);
 
-        assert!(
-            (copies - decrease_copies.0) >= minted_copies,
-            "Paras: cannot decrease supply, already minted : {}",
-            minted_copies
+        if creator_id.is_some() {
+            assert_eq!(
+                creator_id.unwrap().to_string(),
+                caller_id,
+                "Paras: Caller is not creator_id"
+            );
+        }
+
+        let token_series_id = format!("{}", (self.token_series_by_id.len() + 1));
+
+        assert!(
+            self.token_series_by_id.get(&token_series_id).is_none(),
+            "Paras: duplicate token_series_id"
         );
 
-        let is_non_mintable = if (copies - decrease_copies.0) == minted_copies {
-            token_series.is_mintable = false;
-            true
+        let title = token_metadata.title.clone();
+        assert!(title.is_some(), "Paras: token_metadata.title is required");
+
+        let mut total_perpetual = 0;
+        let mut total_accounts = 0;
+        let royalty_res: HashMap<AccountId, u32> = if let Some(royalty) = royalty {
+            for (k, v) in royalty.iter() {
+                if !is_valid_account_id(k.as_bytes()) {
+                    env::panic("Not valid account_id for royalty".as_bytes());
+                };
+                total_perpetual += *v;
+                total_accounts += 1;
+            }
+            royalty
         } else {
-            false
+            HashMap::new()
         };
 
-        token_series.metadata.copies = Some(copies - decrease_copies.0);
+        assert!(total_accounts <= 10, "Paras: royalty exceeds 10 accounts");
 
-        self.token_series_by_id
-            .insert(&token_series_id, &token_series);
-        env::log(
-            json!({
-                "type": "nft_decrease_series_copies",
-                "params": {
-                    "token_series_id": token_series_id,
-                    "copies": U64::from(token_series.metadata.copies.unwrap()),
-                    "is_non_mintable": is_non_mintable,
-                }
-            })
-            .to_string()
-            .as_bytes(),
+        assert!(
+            total_perpetual <= 9000,
+            "Paras Exceeds maximum royalty -> 9000",
         );
-        U64::from(token_series.metadata.copies.unwrap())
+
+        let price_res: Option<u128> = if price.is_some() {
+            self.assert_not_raffle_series(&token_series_id);
+            assert!(
+                price.unwrap().0 < MAX_PRICE,
+                "Paras: price higher than {}",
+                MAX_PRICE
+            );
+            Some(price.unwrap().0)
+        } else {
+            None
+        };
+
+        self.token_series_by_id.insert(
+            &token_series_id,
+            &TokenSeries {
+                metadata: token_metadata.clone(),
+                creator_id: caller_id.to_string(),
+                tokens: UnorderedSet::new(
+                    StorageKey::TokensBySeriesInner {
+                        token_series: token_series_id.clone(),
+                    }
+                    .try_to_vec()
+                    .unwrap(),
+                ),
+                price: price_res,
+                is_mintable: true,
+                royalty: royalty_res.clone(),
+            },
+        );
+
+        // set market data transaction fee
+        let current_transaction_fee = self.calculate_current_transaction_fee();
+        self.market_data_transaction_fee
+            .transaction_fee
+            .insert(&token_series_id, &current_transaction_fee);
+
+        NearEvent::paras_series(ParasSeriesEventKind::NftCreateSeries(vec![
+            event::NftCreateSeriesData {
+                token_series_id: token_series_id.clone(),
+                token_metadata: token_metadata.clone(),
+                creator_id: caller_id.clone(),
+                price,
+                royalty: royalty_res.clone(),
+                transaction_fee: current_transaction_fee.to_string(),
+            },
+        ]))
+        .log();
+
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+
+        TokenSeriesJson {
+            token_series_id,
+            metadata: token_metadata,
+            creator_id: caller_id.into(),
+            royalty: royalty_res,
+            transaction_fee: Some(current_transaction_fee.into()),
+        }
     }
 
     #[payable]
-    pub fn nft_set_series_price(
+    pub fn nft_create_series_custom(
         &mut self,
         token_series_id: TokenSeriesId,
+        creator_id: Option<ValidAccountId>,
+        token_metadata: TokenMetadata,
         price: Option<U128>,
-    ) -> Option<U128> {
-        assert_one_yocto();
+        royalty: Option<HashMap<AccountId, u32>>,
+    ) -> TokenSeriesJson {
+        let initial_storage_usage = env::storage_usage();
+        let caller_id = env::predecessor_account_id();
 
-        let mut token_series = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .expect("Token series not exist");
-        assert_eq!(
-            env::predecessor_account_id(),
-            token_series.creator_id,
-            "Paras: Creator only"
-        );
+        self.assert_role(Role::Minter);
 
-        assert_eq!(
-            token_series.is_mintable, true,
-            "Paras: token series is not mintable"
+        if creator_id.is_some() {
+            assert_eq!(
+                creator_id.unwrap().to_string(),
+                caller_id,
+                "Paras: Caller is not creator_id"
+            );
+        }
+
+        assert!(
+            self.token_series_by_id.get(&token_series_id).is_none(),
+            "Paras: duplicate token_series_id"
         );
 
-        if price.is_none() {
-            token_series.price = None;
+        let title = token_metadata.title.clone();
+        assert!(title.is_some(), "Paras: token_metadata.title is required");
+
+        let mut total_perpetual = 0;
+        let mut total_accounts = 0;
+        let royalty_res: HashMap<AccountId, u32> = if let Some(royalty) = royalty {
+            for (k, v) in royalty.iter() {
+                if !is_valid_account_id(k.as_bytes()) {
+                    env::panic("Not valid account_id for royalty".as_bytes());
+                };
+                total_perpetual += *v;
+                total_accounts += 1;
+            }
+            royalty
         } else {
+            HashMap::new()
+        };
+
+        assert!(total_accounts <= 10, "Paras: royalty exceeds 10 accounts");
+
+        assert!(
+            total_perpetual <= 9000,
+            "Paras Exceeds maximum royalty -> 9000",
+        );
+
+        let price_res: Option<u128> = if price.is_some() {
+            self.assert_not_raffle_series(&token_series_id);
             assert!(
                 price.unwrap().0 < MAX_PRICE,
                 "Paras: price higher than {}",
                 MAX_PRICE
             );
-            token_series.price = Some(price.unwrap().0);
-        }
+            Some(price.unwrap().0)
+        } else {
+            None
+        };
 
-        self.token_series_by_id
-            .insert(&token_series_id, &token_series);
+        self.token_series_by_id.insert(
+            &token_series_id,
+            &TokenSeries {
+                metadata: token_metadata.clone(),
+                creator_id: caller_id.to_string(),
+                tokens: UnorderedSet::new(
+                    StorageKey::TokensBySeriesInner {
+                        token_series: token_series_id.clone(),
+                    }
+                    .try_to_vec()
+                    .unwrap(),
+                ),
+                price: price_res,
+                is_mintable: true,
+                royalty: royalty_res.clone(),
+            },
+        );
 
         // set market data transaction fee
         let current_transaction_fee = self.calculate_current_transaction_fee();
@@ -1033,649 +1716,3139 @@ impl Contract {
             .transaction_fee
             .insert(&token_series_id, &current_transaction_fee);
 
-        env::log(
-            json!({
-                "type": "nft_set_series_price",
-                "params": {
-                    "token_series_id": token_series_id,
-                    "price": price,
-                    "transaction_fee": current_transaction_fee.to_string()
-                }
-            })
-            .to_string()
-            .as_bytes(),
-        );
-        return price;
-    }
-
-    #[payable]
-    pub fn nft_burn(&mut self, token_id: TokenId) {
-        assert_one_yocto();
+        NearEvent::paras_series(ParasSeriesEventKind::NftCreateSeries(vec![
+            event::NftCreateSeriesData {
+                token_series_id: token_series_id.clone(),
+                token_metadata: token_metadata.clone(),
+                creator_id: caller_id.clone(),
+                price,
+                royalty: royalty_res.clone(),
+                transaction_fee: current_transaction_fee.to_string(),
+            },
+        ]))
+        .log();
 
-        let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap();
-        assert_eq!(owner_id, env::predecessor_account_id(), "Token owner only");
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
 
-        if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
-            next_approval_id_by_id.remove(&token_id);
+        TokenSeriesJson {
+            token_series_id,
+            metadata: token_metadata,
+            creator_id: caller_id.into(),
+            royalty: royalty_res,
+            transaction_fee: Some(current_transaction_fee.into()),
         }
+    }
 
-        if let Some(approvals_by_id) = &mut self.tokens.approvals_by_id {
-            approvals_by_id.remove(&token_id);
+    #[payable]
+    pub fn nft_buy(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        receiver_id: ValidAccountId,
+    ) -> TokenId {
+        self.assert_minting_not_paused();
+        let initial_storage_usage = env::storage_usage();
+        let attached_deposit = env::attached_deposit();
+        let token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Paras: Token series not exist");
+        self.assert_not_raffle_series(&token_series_id);
+        let price: u128 = token_series.price.expect("Paras: not for sale");
+        assert!(
+            attached_deposit >= price,
+            "Paras: attached deposit is less than price : {}",
+            price
+        );
+        let token_id: TokenId =
+            self._nft_mint_series(token_series_id.clone(), receiver_id.to_string());
+
+        // primary sale: creator gets the price minus the fee locked for the series
+        let for_treasury =
+            price * self.calculate_market_data_transaction_fee(&token_series_id) / 10_000u128;
+        let price_deducted = price - for_treasury;
+        if price_deducted != 0 {
+            Promise::new(token_series.creator_id.clone()).transfer(price_deducted);
         }
-
-        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
-            let mut token_ids = tokens_per_owner.get(&owner_id).unwrap();
-            token_ids.remove(&token_id);
-            tokens_per_owner.insert(&owner_id, &token_ids);
+        if for_treasury != 0 {
+            Promise::new(self.treasury_id.clone()).transfer(for_treasury);
         }
 
-        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
-            token_metadata_by_id.remove(&token_id);
-        }
+        refund_deposit(env::storage_usage() - initial_storage_usage, price);
 
-        self.tokens.owner_by_id.remove(&token_id);
+        self.events
+            .nft_mint(receiver_id.to_string(), vec![token_id.clone()], None);
+
+        self.events
+            .log(NearEvent::paras_series(ParasSeriesEventKind::NftBuy(vec![
+                event::NftBuyData {
+                    token_series_id,
+                    token_id: token_id.clone(),
+                    buyer_id: env::predecessor_account_id(),
+                    receiver_id: receiver_id.to_string(),
+                    creator_id: token_series.creator_id,
+                    price: price.to_string(),
+                    transaction_fee: for_treasury.to_string(),
+                },
+            ])));
 
-        NearEvent::log_nft_burn(owner_id, vec![token_id], None, None);
+        token_id
     }
 
-    // CUSTOM VIEWS
+    #[payable]
+    pub fn nft_mint_creator(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        receiver_id: ValidAccountId,
+    ) -> TokenId {
+        self.assert_minting_not_paused();
+        let initial_storage_usage = env::storage_usage();
 
-    pub fn nft_get_series_single(&self, token_series_id: TokenSeriesId) -> TokenSeriesJson {
         let token_series = self
             .token_series_by_id
             .get(&token_series_id)
-            .expect("Series does not exist");
-        let current_transaction_fee = self.get_market_data_transaction_fee(&token_series_id);
-        TokenSeriesJson {
-            token_series_id,
-            metadata: token_series.metadata,
-            creator_id: token_series.creator_id,
-            royalty: token_series.royalty,
-            transaction_fee: Some(current_transaction_fee.into()),
-        }
-    }
+            .expect("Paras: Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            "Paras: not creator"
+        );
+        let token_id: TokenId = self._nft_mint_series(token_series_id, receiver_id.to_string());
 
-    pub fn nft_get_series_format(self) -> (char, &'static str, &'static str) {
-        (TOKEN_DELIMETER, TITLE_DELIMETER, EDITION_DELIMETER)
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+
+        self.events
+            .nft_mint(receiver_id.to_string(), vec![token_id.clone()], None);
+
+        token_id
     }
 
-    pub fn nft_get_series_price(self, token_series_id: TokenSeriesId) -> Option<U128> {
-        let price = self.token_series_by_id.get(&token_series_id).unwrap().price;
-        match price {
-            Some(p) => return Some(U128::from(p)),
-            None => return None,
+    //draw a token from a token series
+    #[payable]
+    pub fn draw_and_mint(
+        &mut self,
+        receiver_id: ValidAccountId,
+        merkle_proof: Option<MerkleProof>,
+    ) -> TokenId {
+        self.assert_minting_not_paused();
+        let initial_storage_usage = env::storage_usage();
+        self.internal_draw_and_mint(receiver_id, merkle_proof, 1, &[], initial_storage_usage)
+            .remove(0)
+    }
+
+    /// Draws `quantity` different series in one call, storage and price are charged once
+    #[payable]
+    pub fn draw_and_mint_many(
+        &mut self,
+        receiver_id: ValidAccountId,
+        quantity: u32,
+        merkle_proof: Option<MerkleProof>,
+    ) -> Vec<TokenId> {
+        self.assert_minting_not_paused();
+        assert!(
+            quantity > 0 && quantity <= MAX_DRAW_PER_CALL,
+            "Paras: quantity must be between 1 and {}",
+            MAX_DRAW_PER_CALL
+        );
+        let initial_storage_usage = env::storage_usage();
+        self.internal_draw_and_mint(
+            receiver_id,
+            merkle_proof,
+            quantity,
+            &[],
+            initial_storage_usage,
+        )
+    }
+
+    /// First half of a commit-reveal draw, `commitment` is sha256 of a secret
+    /// that `reveal_and_mint` mixes into the draw from a later block
+    #[payable]
+    pub fn commit_draw(&mut self, commitment: Base64VecU8) {
+        self.assert_minting_not_paused();
+        let initial_storage_usage = env::storage_usage();
+        assert_eq!(
+            commitment.0.len(),
+            32,
+            "Paras: commitment must be a sha256 hash"
+        );
+
+        let caller = env::predecessor_account_id();
+        let block_height = env::block_index();
+        self.draw_commitments.insert(
+            &caller,
+            &DrawCommitment {
+                commitment: commitment.0.clone(),
+                block_height,
+            },
+        );
+
+        NearEvent::paras_series(ParasSeriesEventKind::CommitDraw(vec![
+            event::CommitDrawData {
+                account_id: caller,
+                commitment,
+                block_height: U64(block_height),
+            },
+        ]))
+        .log();
+
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            0,
+        );
+    }
+
+    #[payable]
+    pub fn reveal_and_mint(
+        &mut self,
+        receiver_id: ValidAccountId,
+        secret: Base64VecU8,
+        merkle_proof: Option<MerkleProof>,
+    ) -> TokenId {
+        self.assert_minting_not_paused();
+        // measured before the commitment goes, so its storage counts against the mint
+        let initial_storage_usage = env::storage_usage();
+        let caller = env::predecessor_account_id();
+        let draw_commitment = self
+            .draw_commitments
+            .remove(&caller)
+            .expect("Paras: no draw commitment");
+        assert!(
+            env::block_index() > draw_commitment.block_height,
+            "Paras: reveal must happen after the commit block"
+        );
+        assert_eq!(
+            env::sha256(&secret.0),
+            draw_commitment.commitment,
+            "Paras: secret does not match commitment"
+        );
+
+        self.internal_draw_and_mint(
+            receiver_id,
+            merkle_proof,
+            1,
+            &secret.0,
+            initial_storage_usage,
+        )
+        .remove(0)
+    }
+
+    pub fn get_draw_commitment_block(&self, account_id: AccountId) -> Option<U64> {
+        self.draw_commitments
+            .get(&account_id)
+            .map(|draw_commitment| U64(draw_commitment.block_height))
+    }
+
+    fn internal_draw_and_mint(
+        &mut self,
+        receiver_id: ValidAccountId,
+        merkle_proof: Option<MerkleProof>,
+        quantity: u32,
+        secret: &[u8],
+        initial_storage_usage: u64,
+    ) -> Vec<TokenId> {
+        let caller = env::predecessor_account_id();
+        // log(token_series_id.as_bytes());
+
+        if self.get_raffle_length() == 0 {
+            panic!("Sold Out")
+        }
+        // weighted draws share one read of the pool, drawn entries get weight 0
+        // so a call never draws the same series twice
+        let mut weights = match self.raffle_mode {
+            RaffleMode::Uniform => None,
+            RaffleMode::Weighted => Some(self.internal_raffle_weights()),
         };
+        let left_to_draw = match &weights {
+            None => self.get_raffle_length(),
+            Some(weights) => weights.iter().filter(|weight| **weight > 0).count() as u64,
+        };
+        assert!(
+            left_to_draw >= quantity as u64,
+            "Paras: only {} left to draw",
+            left_to_draw
+        );
+
+        let terms = self.internal_mint_terms(&caller, merkle_proof.as_ref(), false, quantity);
+        let price = terms.price * quantity as u128;
+        assert!(
+            env::attached_deposit() >= price,
+            "Paras: attached deposit is less than price : {}",
+            price
+        );
+
+        let mut token_ids: Vec<TokenId> = vec![];
+        for _ in 0..quantity {
+            let token_series_id = self.internal_raffle_draw(&caller, secret, weights.as_mut());
+            // let token_series = self.token_series_by_id.get(&token_series_id).expect("Paras: Token series not exist");
+            // assert_eq!(env::predecessor_account_id(), token_series.creator_id, "Paras: not creator");
+            token_ids.push(self._nft_mint_series(token_series_id, receiver_id.to_string()));
+        }
+
+        self.internal_record_mint(&caller, &terms);
+
+        // ext_whitelist_contract::incress_balance_whitelist(
+        //     caller.clone(),
+        //     &self.whitelist_contract_id.clone(),
+        //     NO_DEPOSIT,
+        //     GAS_FOR_RESOLVE_TRANSFER,
+        // );
+
+        self.internal_transfer_mint_price(price);
+
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            price,
+        );
+
+        self.events
+            .nft_mint(receiver_id.to_string(), token_ids.clone(), None);
+
+        token_ids
     }
 
-    pub fn nft_get_series(
-        &self,
-        from_index: Option<U128>,
-        limit: Option<u64>,
-    ) -> Vec<TokenSeriesJson> {
-        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+    //custom mint token series
+    #[payable]
+    pub fn nft_mint(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        receiver_id: ValidAccountId,
+        merkle_proof: Option<MerkleProof>,
+    ) -> TokenId {
+        self.assert_minting_not_paused();
+        let caller = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        // log(token_series_id.as_bytes());
+        let terms = self.internal_mint_terms(&caller, merkle_proof.as_ref(), true, 1);
+        let price = terms.price;
         assert!(
-            (self.token_series_by_id.len() as u128) > start_index,
-            "Out of bounds, please use a smaller from_index."
+            env::attached_deposit() >= price,
+            "Paras: attached deposit is less than price : {}",
+            price
+        );
+
+        self.token_series_by_id
+            .get(&token_series_id)
+            .expect("Paras: Token series not exist");
+        // let token_series = self.token_series_by_id.get(&token_series_id).expect("Paras: Token series not exist");
+        // assert_eq!(env::predecessor_account_id(), token_series.creator_id, "Paras: not creator");
+        let token_id: TokenId = self._nft_mint_series(token_series_id, receiver_id.to_string());
+
+        //decrease balance in OG
+        self.internal_record_mint(&caller, &terms);
+
+        self.internal_transfer_mint_price(price);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage, price);
+
+        self.events
+            .nft_mint(receiver_id.to_string(), vec![token_id.clone()], None);
+
+        token_id
+    }
+
+    #[payable]
+    pub fn nft_mint_and_approve(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        account_id: ValidAccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        self.assert_minting_not_paused();
+        let initial_storage_usage = env::storage_usage();
+
+        let token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Paras: Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            "Paras: not creator"
+        );
+        let token_id: TokenId =
+            self._nft_mint_series(token_series_id, token_series.creator_id.clone());
+
+        // Need to copy the nft_approve code here to solve the gas problem
+        // get contract-level LookupMap of token_id to approvals HashMap
+        let approvals_by_id = self.tokens.approvals_by_id.as_mut().unwrap();
+
+        // update HashMap of approvals for this token
+        let approved_account_ids = &mut approvals_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| HashMap::new());
+        let account_id: AccountId = account_id.into();
+        let approval_id: u64 = self
+            .tokens
+            .next_approval_id_by_id
+            .as_ref()
+            .unwrap()
+            .get(&token_id)
+            .unwrap_or_else(|| 1u64);
+        approved_account_ids.insert(account_id.clone(), approval_id);
+
+        // save updated approvals HashMap to contract's LookupMap
+        approvals_by_id.insert(&token_id, &approved_account_ids);
+
+        // increment next_approval_id for this token
+        self.tokens
+            .next_approval_id_by_id
+            .as_mut()
+            .unwrap()
+            .insert(&token_id, &(approval_id + 1));
+
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+
+        self.events.nft_mint(
+            token_series.creator_id.clone(),
+            vec![token_id.clone()],
+            None,
+        );
+
+        if let Some(msg) = msg {
+            Some(ext_approval_receiver::nft_on_approve(
+                token_id,
+                token_series.creator_id,
+                approval_id,
+                msg,
+                &account_id,
+                NO_DEPOSIT,
+                env::prepaid_gas() - GAS_FOR_NFT_APPROVE - GAS_FOR_MINT,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Draws from the raffle with the caller, block height and commit-reveal secret as
+    /// extra entropy, and logs every input so the distribution can be audited.
+    /// `weights` is given in weighted mode and kept in step with the pool.
+    fn internal_raffle_draw(
+        &mut self,
+        account_id: &AccountId,
+        secret: &[u8],
+        weights: Option<&mut Vec<u64>>,
+    ) -> TokenSeriesId {
+        let block_height = env::block_index();
+        let nonce = self.raffle.nonce();
+        let pool_size = self.raffle.len();
+        let entropy = [
+            account_id.as_bytes(),
+            &block_height.to_le_bytes()[..],
+            secret,
+        ]
+        .concat();
+
+        let drawn = match weights {
+            None => self.raffle.draw(&entropy),
+            Some(weights) => {
+                let index = self.raffle.pick_weighted(&entropy, &weights[..]);
+                let drawn = self.raffle.get(index).unwrap();
+                // the series leaves the pool with its last copy
+                let copies_left = self.internal_copies_left(&self.internal_raffle_series(drawn));
+                if copies_left.map_or(false, |copies_left| copies_left <= 1) {
+                    self.raffle.swap_remove(index);
+                    weights.swap_remove(index as usize);
+                } else {
+                    weights[index as usize] = 0;
+                }
+                drawn
+            }
+        };
+        let token_series_id = self.internal_raffle_series(drawn);
+        // panicking reverts the draw, the entry stays in the pool until reserved
+        assert!(
+            self.token_series_by_id.get(&token_series_id).is_some(),
+            "Paras: raffle drew token series {} that does not exist",
+            token_series_id
+        );
+
+        self.events
+            .log(NearEvent::paras_series(ParasSeriesEventKind::RaffleDraw(
+                vec![event::RaffleDrawData {
+                    account_id: account_id.clone(),
+                    block_height: U64(block_height),
+                    random_seed: Base64VecU8::from(env::random_seed()),
+                    nonce: U64(nonce),
+                    secret: Base64VecU8::from(secret.to_vec()),
+                    raffle_mode: self.raffle_mode,
+                    pool_size: U64(pool_size),
+                    drawn: U64(drawn),
+                    token_series_id: token_series_id.clone(),
+                }],
+            )));
+
+        token_series_id
+    }
+
+    fn internal_transfer_mint_price(&self, price: Balance) {
+        if price != 0 {
+            Promise::new(self.treasury_id.clone()).transfer(price);
+        }
+    }
+
+    fn _nft_mint_series(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        receiver_id: AccountId,
+    ) -> TokenId {
+        let mut token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Paras: Token series not exist");
+        assert!(
+            token_series.is_mintable,
+            "Paras: Token series is not mintable"
+        );
+
+        let num_tokens = token_series.tokens.len();
+        let max_copies = token_series.metadata.copies.unwrap_or(u64::MAX);
+        assert!(num_tokens < max_copies, "Series supply maxed");
+
+        if (num_tokens + 1) >= max_copies {
+            token_series.is_mintable = false;
+        }
+
+        let token_id = format!("{}{}{}", &token_series_id, TOKEN_DELIMETER, num_tokens + 1);
+        token_series.tokens.insert(&token_id);
+        self.token_series_by_id
+            .insert(&token_series_id, &token_series);
+        if !token_series.is_mintable {
+            self.internal_raffle_remove_minted_out(&token_series_id);
+        }
+
+        // you can add custom metadata to each token here
+        let metadata = Some(TokenMetadata {
+            title: None,       // ex. "Arch Nemesis: Mail Carrier" or "Parcel #5055"
+            description: None, // free-form description
+            media: None, // URL to associated media, preferably to decentralized, content-addressed storage
+            media_hash: None, // Base64-encoded sha256 hash of content referenced by the `media` field. Required if `media` is included.
+            copies: None, // number of copies of this set of metadata in existence when token was minted.
+            issued_at: Some(env::block_timestamp().to_string()), // ISO 8601 datetime when token was issued or minted
+            expires_at: None,     // ISO 8601 datetime when token expires
+            starts_at: None,      // ISO 8601 datetime when token starts being valid
+            updated_at: None,     // ISO 8601 datetime when token was last updated
+            extra: None, // anything extra the NFT wants to store on-chain. Can be stringified JSON.
+            reference: None, // URL to an off-chain JSON file with more info.
+            reference_hash: None, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
+        });
+
+        //let token = self.tokens.mint(token_id, receiver_id, metadata);
+        // From : https://github.com/near/near-sdk-rs/blob/master/near-contract-standards/src/non_fungible_token/core/core_impl.rs#L359
+        // This allows lazy minting
+
+        let owner_id: AccountId = receiver_id;
+        self.tokens.owner_by_id.insert(&token_id, &owner_id);
+
+        self.tokens
+            .token_metadata_by_id
+            .as_mut()
+            .and_then(|by_id| by_id.insert(&token_id, &metadata.as_ref().unwrap()));
+
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            let mut token_ids = tokens_per_owner.get(&owner_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::TokensPerOwner {
+                    account_hash: env::sha256(&owner_id.as_bytes()),
+                })
+            });
+            token_ids.insert(&token_id);
+            tokens_per_owner.insert(&owner_id, &token_ids);
+        }
+
+        self.token_series_id_minted = self.token_series_id_minted + 1;
+
+        //cross contract call to update balance whitelist
+
+        token_id
+    }
+
+    #[payable]
+    pub fn nft_set_series_non_mintable(&mut self, token_series_id: TokenSeriesId) {
+        assert_one_yocto();
+
+        let mut token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            "Paras: Creator only"
+        );
+
+        assert_eq!(
+            token_series.is_mintable, true,
+            "Paras: already non-mintable"
+        );
+
+        assert_eq!(
+            token_series.metadata.copies, None,
+            "Paras: decrease supply if copies not null"
+        );
+
+        token_series.is_mintable = false;
+        self.token_series_by_id
+            .insert(&token_series_id, &token_series);
+        NearEvent::paras_series(ParasSeriesEventKind::NftSetSeriesNonMintable(vec![
+            event::NftSetSeriesNonMintableData { token_series_id },
+        ]))
+        .log();
+    }
+
+    #[payable]
+    pub fn nft_decrease_series_copies(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        decrease_copies: U64,
+    ) -> U64 {
+        assert_one_yocto();
+
+        let mut token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            "Paras: Creator only"
+        );
+
+        let minted_copies = token_series.tokens.len();
+        let copies = token_series.metadata.copies.unwrap();
+
+        assert!(
+            (copies - decrease_copies.0) >= minted_copies,
+            "Paras: cannot decrease supply, already minted : {}",
+            minted_copies
+        );
+
+        let is_non_mintable = if (copies - decrease_copies.0) == minted_copies {
+            token_series.is_mintable = false;
+            true
+        } else {
+            false
+        };
+
+        token_series.metadata.copies = Some(copies - decrease_copies.0);
+
+        self.token_series_by_id
+            .insert(&token_series_id, &token_series);
+        NearEvent::paras_series(ParasSeriesEventKind::NftDecreaseSeriesCopies(vec![
+            event::NftDecreaseSeriesCopiesData {
+                token_series_id,
+                copies: U64::from(token_series.metadata.copies.unwrap()),
+                is_non_mintable,
+            },
+        ]))
+        .log();
+        U64::from(token_series.metadata.copies.unwrap())
+    }
+
+    #[payable]
+    pub fn nft_set_series_price(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        price: Option<U128>,
+    ) -> Option<U128> {
+        assert_one_yocto();
+
+        let mut token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            "Paras: Creator only"
+        );
+
+        assert_eq!(
+            token_series.is_mintable, true,
+            "Paras: token series is not mintable"
+        );
+
+        if price.is_none() {
+            token_series.price = None;
+        } else {
+            self.assert_not_raffle_series(&token_series_id);
+            assert!(
+                price.unwrap().0 < MAX_PRICE,
+                "Paras: price higher than {}",
+                MAX_PRICE
+            );
+            token_series.price = Some(price.unwrap().0);
+        }
+
+        self.token_series_by_id
+            .insert(&token_series_id, &token_series);
+
+        // set market data transaction fee
+        let current_transaction_fee = self.calculate_current_transaction_fee();
+        self.market_data_transaction_fee
+            .transaction_fee
+            .insert(&token_series_id, &current_transaction_fee);
+
+        NearEvent::paras_series(ParasSeriesEventKind::NftSetSeriesPrice(vec![
+            event::NftSetSeriesPriceData {
+                token_series_id,
+                price,
+                transaction_fee: current_transaction_fee.to_string(),
+            },
+        ]))
+        .log();
+        return price;
+    }
+
+    /// Sets fields of a single edition that differ from its series, `None` clears them.
+    /// copies always come from the series. media can be overridden together with its
+    /// media_hash, except while the collection is hidden or once a provenance hash is
+    /// committed, as that hash covers the media of every series.
+    #[payable]
+    pub fn nft_set_token_metadata(&mut self, token_id: TokenId, metadata: Option<TokenMetadata>) {
+        let initial_storage_usage = env::storage_usage();
+        assert!(
+            self.tokens.owner_by_id.get(&token_id).is_some(),
+            "Paras: Token not exist"
+        );
+
+        let token_series_id: TokenSeriesId =
+            token_id.split(TOKEN_DELIMETER).next().unwrap().to_string();
+        let token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            "Paras: Creator only"
+        );
+        if let Some(metadata) = &metadata {
+            assert!(
+                metadata.copies.is_none(),
+                "Paras: copies always come from the series"
+            );
+            assert_eq!(
+                metadata.media.is_some(),
+                metadata.media_hash.is_some(),
+                "Paras: media and media_hash are overridden together"
+            );
+            if metadata.media.is_some() {
+                assert!(
+                    self.revealed && self.provenance_hash.is_none(),
+                    "Paras: media is frozen while hidden or under a provenance hash"
+                );
+            }
+        }
+
+        match &metadata {
+            Some(metadata) => self.token_metadata_overrides.insert(&token_id, metadata),
+            None => self.token_metadata_overrides.remove(&token_id),
+        };
+
+        NearEvent::log_nft_metadata_update(vec![token_id.clone()], None);
+        NearEvent::paras_series(ParasSeriesEventKind::NftSetTokenMetadata(vec![
+            event::NftSetTokenMetadataData { token_id, metadata },
+        ]))
+        .log();
+
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            0,
+        );
+    }
+
+    #[payable]
+    pub fn set_edition_title(&mut self, edition_title: bool) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.edition_title = edition_title;
+
+        NearEvent::paras_admin(ParasAdminEventKind::SetEditionTitle(vec![
+            event::SetEditionTitleData { edition_title },
+        ]))
+        .log();
+    }
+
+    pub fn get_edition_title(&self) -> bool {
+        self.edition_title
+    }
+
+    #[payable]
+    pub fn nft_burn(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+
+        let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap();
+        assert_eq!(owner_id, env::predecessor_account_id(), "Token owner only");
+
+        if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
+            next_approval_id_by_id.remove(&token_id);
+        }
+
+        if let Some(approvals_by_id) = &mut self.tokens.approvals_by_id {
+            approvals_by_id.remove(&token_id);
+        }
+
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            let mut token_ids = tokens_per_owner.get(&owner_id).unwrap();
+            token_ids.remove(&token_id);
+            tokens_per_owner.insert(&owner_id, &token_ids);
+        }
+
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            token_metadata_by_id.remove(&token_id);
+        }
+
+        self.tokens.owner_by_id.remove(&token_id);
+        self.token_metadata_overrides.remove(&token_id);
+
+        self.events.nft_burn(owner_id, vec![token_id], None, None);
+    }
+
+    // Metadata updates
+
+    /// Changes the given contract metadata fields, the others are left as they are
+    #[payable]
+    pub fn nft_update_contract_metadata(
+        &mut self,
+        base_uri: Option<String>,
+        icon: Option<String>,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    ) {
+        let initial_storage_usage = env::storage_usage();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.tokens.owner_id,
+            "Paras: Owner only"
+        );
+
+        let mut metadata = self.metadata.get().unwrap();
+        if base_uri.is_some() {
+            metadata.base_uri = base_uri;
+        }
+        if icon.is_some() {
+            metadata.icon = icon;
+        }
+        if reference.is_some() {
+            metadata.reference = reference;
+        }
+        if reference_hash.is_some() {
+            metadata.reference_hash = reference_hash;
+        }
+        metadata.assert_valid();
+        self.metadata.set(&metadata);
+
+        NearEvent::log_contract_metadata_update(None);
+
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            0,
+        );
+    }
+
+    /// Replaces the series metadata while nothing is minted. Afterwards only the description
+    /// and the media / reference locations can change, their hashes have to stay the same.
+    /// Once a drop with a provenance hash starts minting, media, media_hash and extra of every
+    /// series are frozen, minted or not, since the hash covers all of them.
+    /// `copies` is left to `nft_decrease_series_copies`. Editions of series with more than
+    /// `MAX_METADATA_UPDATE_TOKEN_IDS` tokens are left out of `nft_metadata_update`,
+    /// `nft_log_series_metadata_update` logs them page by page.
+    #[payable]
+    pub fn nft_update_series_metadata(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        metadata: TokenMetadata,
+    ) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_role(Role::Admin);
+
+        let mut token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Token series not exist");
+        assert!(metadata.title.is_some(), "Paras: title is required");
+
+        let mut metadata = metadata;
+        metadata.copies = token_series.metadata.copies;
+        if token_series.tokens.len() > 0 {
+            let current = &token_series.metadata;
+            assert!(
+                metadata.title == current.title
+                    && metadata.media_hash == current.media_hash
+                    && metadata.reference_hash == current.reference_hash
+                    && metadata.extra == current.extra,
+                "Paras: only description, media and reference can change after the first mint"
+            );
+        }
+        if self.provenance_hash.is_some() && self.token_series_id_minted > 0 {
+            let current = &token_series.metadata;
+            assert!(
+                metadata.media == current.media
+                    && metadata.media_hash == current.media_hash
+                    && metadata.extra == current.extra,
+                "Paras: media, media_hash and extra are frozen once the drop has started minting"
+            );
+        }
+
+        token_series.metadata = metadata.clone();
+        self.token_series_by_id
+            .insert(&token_series_id, &token_series);
+
+        NearEvent::paras_series(ParasSeriesEventKind::NftUpdateSeriesMetadata(vec![
+            event::NftUpdateSeriesMetadataData {
+                token_series_id,
+                metadata,
+            },
+        ]))
+        .log();
+        let num_tokens = token_series.tokens.len();
+        if num_tokens > 0 && num_tokens <= MAX_METADATA_UPDATE_TOKEN_IDS {
+            NearEvent::log_nft_metadata_update(token_series.tokens.to_vec(), None);
+        }
+
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            0,
+        );
+    }
+
+    /// Logs `nft_metadata_update` for up to `MAX_METADATA_UPDATE_TOKEN_IDS` editions of the series
+    #[payable]
+    pub fn nft_log_series_metadata_update(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        let token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Token series not exist");
+
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        assert!(
+            (token_series.tokens.len() as u128) > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        let limit = limit
+            .unwrap_or(MAX_METADATA_UPDATE_TOKEN_IDS)
+            .min(MAX_METADATA_UPDATE_TOKEN_IDS) as usize;
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+
+        let token_ids: Vec<TokenId> = token_series
+            .tokens
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .collect();
+        NearEvent::log_nft_metadata_update(token_ids, None);
+    }
+
+    // Reveal
+
+    /// Hides every series behind `placeholder` until `reveal`, only before the first mint.
+    /// The provenance hash goes first, a collection hidden without one could never be revealed
+    #[payable]
+    pub fn set_placeholder_metadata(&mut self, placeholder: TokenMetadata) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_role(Role::Admin);
+        assert!(
+            !self.revealed || self.token_series_id_minted == 0,
+            "Paras: tokens already minted with revealed metadata"
+        );
+        assert!(
+            self.provenance_hash.is_some(),
+            "Paras: provenance hash must be set before hiding the collection"
+        );
+        assert!(
+            placeholder.title.is_some(),
+            "Paras: placeholder title is required"
+        );
+
+        self.placeholder_metadata.set(&placeholder);
+        self.revealed = false;
+
+        NearEvent::paras_admin(ParasAdminEventKind::SetPlaceholderMetadata(vec![
+            event::SetPlaceholderMetadataData { placeholder },
+        ]))
+        .log();
+
+        refund_deposit(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            0,
+        );
+    }
+
+    pub fn get_placeholder_metadata(&self) -> Option<TokenMetadata> {
+        self.placeholder_metadata.get()
+    }
+
+    #[payable]
+    pub fn set_provenance_hash(&mut self, provenance_hash: Base64VecU8) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert_eq!(
+            self.token_series_id_minted, 0,
+            "Paras: provenance hash is frozen after the first mint"
+        );
+        assert_eq!(
+            provenance_hash.0.len(),
+            32,
+            "Paras: provenance hash must be a sha256 hash"
+        );
+        self.provenance_hash = Some(provenance_hash.clone().into());
+
+        NearEvent::paras_admin(ParasAdminEventKind::SetProvenanceHash(vec![
+            event::SetProvenanceHashData { provenance_hash },
+        ]))
+        .log();
+    }
+
+    pub fn get_provenance_hash(&self) -> Option<Base64VecU8> {
+        self.provenance_hash.clone().map(Base64VecU8::from)
+    }
+
+    /// Recomputes the provenance hash from the stored series and compares it,
+    /// false when it isn't set or a series has no media_hash
+    pub fn verify_provenance(&self) -> bool {
+        let provenance_hash = match &self.provenance_hash {
+            Some(provenance_hash) => provenance_hash,
+            None => return false,
+        };
+        let mut media_hashes: Vec<u8> = vec![];
+        for (_, token_series) in self.token_series_by_id.iter() {
+            match token_series.metadata.media_hash {
+                Some(media_hash) => media_hashes.extend(media_hash.0),
+                None => return false,
+            }
+        }
+        &env::sha256(&media_hashes) == provenance_hash
+    }
+
+    pub fn is_revealed(&self) -> bool {
+        self.revealed
+    }
+
+    /// Points the contract `base_uri` at the real collection and shows series metadata
+    #[payable]
+    pub fn reveal(&mut self, base_uri_or_reference: String) {
+        assert_one_yocto();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.tokens.owner_id,
+            "Paras: Owner only"
+        );
+        assert!(!self.revealed, "Paras: already revealed");
+        let provenance_hash = self
+            .get_provenance_hash()
+            .expect("Paras: provenance hash must be set before reveal");
+
+        let mut metadata = self.metadata.get().unwrap();
+        metadata.base_uri = Some(base_uri_or_reference.clone());
+        self.metadata.set(&metadata);
+        self.revealed = true;
+
+        NearEvent::paras_admin(ParasAdminEventKind::Reveal(vec![event::RevealData {
+            base_uri: base_uri_or_reference,
+            provenance_hash,
+        }]))
+        .log();
+        NearEvent::log_contract_metadata_update(Some("reveal".to_string()));
+    }
+
+    /// Series metadata as it can be shown right now
+    fn internal_visible_metadata(&self, metadata: TokenMetadata) -> TokenMetadata {
+        if self.revealed {
+            metadata
+        } else {
+            self.placeholder_metadata.get().unwrap_or(metadata)
+        }
+    }
+
+    // CUSTOM VIEWS
+
+    pub fn nft_get_series_single(&self, token_series_id: TokenSeriesId) -> TokenSeriesJson {
+        let token_series = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .expect("Series does not exist");
+        let current_transaction_fee = self.get_market_data_transaction_fee(&token_series_id);
+        TokenSeriesJson {
+            token_series_id,
+            metadata: self.internal_visible_metadata(token_series.metadata),
+            creator_id: token_series.creator_id,
+            royalty: token_series.royalty,
+            transaction_fee: Some(current_transaction_fee.into()),
+        }
+    }
+
+    pub fn nft_get_series_format(self) -> (char, &'static str, &'static str) {
+        (TOKEN_DELIMETER, TITLE_DELIMETER, EDITION_DELIMETER)
+    }
+
+    pub fn nft_get_series_price(self, token_series_id: TokenSeriesId) -> Option<U128> {
+        let price = self.token_series_by_id.get(&token_series_id).unwrap().price;
+        match price {
+            Some(p) => return Some(U128::from(p)),
+            None => return None,
+        };
+    }
+
+    pub fn nft_get_series(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<TokenSeriesJson> {
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        assert!(
+            (self.token_series_by_id.len() as u128) > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+
+        self.token_series_by_id
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .map(|(token_series_id, token_series)| TokenSeriesJson {
+                token_series_id,
+                metadata: self.internal_visible_metadata(token_series.metadata),
+                creator_id: token_series.creator_id,
+                royalty: token_series.royalty,
+                transaction_fee: None,
+            })
+            .collect()
+    }
+
+    pub fn nft_supply_for_series(&self, token_series_id: TokenSeriesId) -> U64 {
+        self.token_series_by_id
+            .get(&token_series_id)
+            .expect("Token series not exist")
+            .tokens
+            .len()
+            .into()
+    }
+
+    pub fn nft_tokens_by_series(
+        &self,
+        token_series_id: TokenSeriesId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Token> {
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        let tokens = self
+            .token_series_by_id
+            .get(&token_series_id)
+            .unwrap()
+            .tokens;
+        assert!(
+            (tokens.len() as u128) > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+
+        tokens
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .map(|token_id| self.nft_token(token_id).unwrap())
+            .collect()
+    }
+
+    pub fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        let owner_id = self.tokens.owner_by_id.get(&token_id)?;
+        let approved_account_ids = self
+            .tokens
+            .approvals_by_id
+            .as_ref()
+            .and_then(|by_id| by_id.get(&token_id).or_else(|| Some(HashMap::new())));
+
+        // CUSTOM (switch metadata for the token_series metadata)
+        let mut token_id_iter = token_id.split(TOKEN_DELIMETER);
+        let token_series_id = token_id_iter.next().unwrap().parse().unwrap();
+        let series_metadata = self.internal_visible_metadata(
+            self.token_series_by_id
+                .get(&token_series_id)
+                .unwrap()
+                .metadata,
+        );
+
+        let mut token_metadata = self
+            .tokens
+            .token_metadata_by_id
+            .as_ref()
+            .unwrap()
+            .get(&token_id)
+            .unwrap();
+
+        if self.edition_title {
+            token_metadata.title = Some(format!(
+                "{}{}{}",
+                series_metadata.title.unwrap(),
+                TITLE_DELIMETER,
+                token_id_iter.next().unwrap()
+            ));
+        } else {
+            token_metadata.title = Some(format!("{}", series_metadata.title.unwrap(),));
+        }
+
+        token_metadata.reference = series_metadata.reference;
+        token_metadata.media = series_metadata.media;
+        token_metadata.copies = series_metadata.copies;
+        token_metadata.extra = series_metadata.extra;
+
+        // overrides would give away what is behind the placeholder
+        if self.revealed {
+            if let Some(token_override) = self.token_metadata_overrides.get(&token_id) {
+                merge_token_metadata(&mut token_metadata, token_override);
+            }
+        }
+
+        Some(Token {
+            token_id,
+            owner_id,
+            metadata: Some(token_metadata),
+            approved_account_ids,
+        })
+    }
+
+    pub fn is_seller(&self, account_id: AccountId) -> bool {
+        let count_sell = if let Some(count) = self.seller_by_id.get(&account_id) {
+            count
+        } else {
+            0
+        };
+
+        count_sell > 0
+    }
+
+    pub fn get_seller(&self) -> Vec<String> {
+        let mut tmp: Vec<String> = vec![];
+        for i in self.seller_by_id.keys() {
+            tmp.push(i);
+        }
+        tmp
+    }
+
+    // CUSTOM core standard repeated here because no macro below
+
+    pub fn nft_transfer_unsafe(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        let sender_id = env::predecessor_account_id();
+        self.assert_transfer_allowed(&sender_id, &token_id);
+        let receiver_id_str = receiver_id.to_string();
+        let (previous_owner_id, _) = self.tokens.internal_transfer(
+            &sender_id,
+            &receiver_id_str,
+            &token_id,
+            approval_id,
+            memo.clone(),
+        );
+
+        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
+            Some(sender_id)
+        } else {
+            None
+        };
+
+        self.events.nft_transfer(
+            previous_owner_id,
+            receiver_id_str,
+            vec![token_id],
+            memo,
+            authorized_id,
+        );
+    }
+
+    #[payable]
+    pub fn nft_transfer(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        let sender_id = env::predecessor_account_id();
+        self.assert_transfer_allowed(&sender_id, &token_id);
+        let previous_owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .expect("Token not found");
+        let receiver_id_str = receiver_id.to_string();
+        self.tokens
+            .nft_transfer(receiver_id, token_id.clone(), approval_id, memo.clone());
+
+        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
+            Some(sender_id)
+        } else {
+            None
+        };
+
+        self.events.nft_transfer(
+            previous_owner_id,
+            receiver_id_str,
+            vec![token_id],
+            memo,
+            authorized_id,
+        );
+    }
+
+    #[payable]
+    pub fn assign_garden(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+    ) -> PromiseOrValue<bool> {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.assert_transfer_allowed(&sender_id, &token_id);
+
+        if !self.is_seller(sender_id.clone()) {
+            panic!("You must sell at least 1 NFT")
+        }
+
+        let (previous_owner_id, old_approvals) =
+            self.tokens
+                .internal_transfer(&sender_id, receiver_id.as_ref(), &token_id, None, None);
+
+        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
+            Some(sender_id.clone())
+        } else {
+            None
+        };
+
+        self.events.nft_transfer(
+            previous_owner_id.clone(),
+            receiver_id.to_string(),
+            vec![token_id.clone()],
+            None,
+            authorized_id,
+        );
+
+        let msg = if self.is_seller(previous_owner_id.clone()) {
+            "seller".to_string()
+        } else {
+            "not_seller".to_string()
+        };
+
+        // Initiating receiver's call and the callback
+        ext_non_fungible_token_receiver::nft_on_transfer(
+            sender_id,
+            previous_owner_id.clone(),
+            token_id.clone(),
+            msg,
+            receiver_id.as_ref(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
+        )
+        .then(ext_self::nft_resolve_transfer(
+            previous_owner_id,
+            receiver_id.into(),
+            token_id,
+            old_approvals,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+        .into()
+    }
+
+    #[payable]
+    pub fn nft_transfer_call(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.assert_transfer_allowed(&sender_id, &token_id);
+        let (previous_owner_id, old_approvals) = self.tokens.internal_transfer(
+            &sender_id,
+            receiver_id.as_ref(),
+            &token_id,
+            approval_id,
+            memo.clone(),
+        );
+
+        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
+            Some(sender_id.clone())
+        } else {
+            None
+        };
+
+        self.events.nft_transfer(
+            previous_owner_id.clone(),
+            receiver_id.to_string(),
+            vec![token_id.clone()],
+            memo,
+            authorized_id,
+        );
+
+        // Initiating receiver's call and the callback
+        ext_non_fungible_token_receiver::nft_on_transfer(
+            sender_id,
+            previous_owner_id.clone(),
+            token_id.clone(),
+            msg,
+            receiver_id.as_ref(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
+        )
+        .then(ext_self::nft_resolve_transfer(
+            previous_owner_id,
+            receiver_id.into(),
+            token_id,
+            old_approvals,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+        .into()
+    }
+
+    // CUSTOM enumeration standard modified here because no macro below
+
+    pub fn nft_total_supply(&self) -> U128 {
+        (self.tokens.owner_by_id.len() as u128).into()
+    }
+
+    pub fn nft_total_series_supply(&self) -> U128 {
+        (self.token_series_by_id.len() as u128).into()
+    }
+
+    pub fn nft_total_series_minted(&self) -> U128 {
+        (self.token_series_id_minted as u128).into()
+    }
+
+    pub fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        // Get starting index, whether or not it was explicitly given.
+        // Defaults to 0 based on the spec:
+        // https://nomicon.io/Standards/NonFungibleToken/Enumeration.html#interface
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        assert!(
+            (self.tokens.owner_by_id.len() as u128) > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+        self.tokens
+            .owner_by_id
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .map(|(token_id, _)| self.nft_token(token_id).unwrap())
+            .collect()
+    }
+
+    pub fn nft_supply_for_owner(self, account_id: ValidAccountId) -> U128 {
+        let tokens_per_owner = self.tokens.tokens_per_owner.expect(
+            "Could not find tokens_per_owner when calling a method on the enumeration standard.",
+        );
+        tokens_per_owner
+            .get(account_id.as_ref())
+            .map(|account_tokens| U128::from(account_tokens.len() as u128))
+            .unwrap_or(U128(0))
+    }
+
+    pub fn nft_tokens_for_owner(
+        &self,
+        account_id: ValidAccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Token> {
+        let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().expect(
+            "Could not find tokens_per_owner when calling a method on the enumeration standard.",
+        );
+        let token_set = if let Some(token_set) = tokens_per_owner.get(account_id.as_ref()) {
+            token_set
+        } else {
+            return vec![];
+        };
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        assert!(
+            token_set.len() as u128 > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        token_set
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .map(|token_id| self.nft_token(token_id).unwrap())
+            .collect()
+    }
+
+    fn internal_series_royalty(&self, token_id: &TokenId) -> HashMap<AccountId, u32> {
+        let token_series_id: TokenSeriesId =
+            token_id.split(TOKEN_DELIMETER).next().unwrap().to_string();
+        self.token_series_by_id
+            .get(&token_series_id)
+            .expect("no type")
+            .royalty
+    }
+
+    fn internal_compute_payout(
+        &self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        balance: Balance,
+        max_len_payout: Option<u32>,
+    ) -> Result<Payout, String> {
+        let royalty = self.internal_series_royalty(token_id);
+        royalty::compute_payout(&royalty, owner_id, balance, max_len_payout)
+    }
+
+    /// Checks shared by the transfers that pay no royalty, run after `assert_one_yocto`
+    fn assert_transfer_allowed(&self, sender_id: &AccountId, token_id: &TokenId) {
+        self.assert_transfers_not_paused();
+        self.assert_royalty_free_transfer(sender_id, token_id);
+    }
+
+    /// Royalty is only paid through `nft_transfer_payout`, so an approved account can't move
+    /// a token that owes royalty any other way. Owners still move their own tokens freely.
+    fn assert_royalty_free_transfer(&self, sender_id: &AccountId, token_id: &TokenId) {
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(token_id)
+            .expect("Token not found");
+        if sender_id == &owner_id {
+            return;
+        }
+        let royalty = self.internal_series_royalty(token_id);
+        assert!(
+            royalty.keys().all(|account_id| account_id == &owner_id),
+            "Paras: approved transfers of tokens with royalty go through nft_transfer_payout"
+        );
+    }
+
+    pub fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: u32) -> Payout {
+        let owner_id = self.tokens.owner_by_id.get(&token_id).expect("No token id");
+        self.internal_compute_payout(&token_id, &owner_id, balance.into(), Some(max_len_payout))
+            .unwrap_or_else(|err| env::panic(err.as_bytes()))
+    }
+
+    #[payable]
+    pub fn nft_transfer_payout(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        balance: Option<U128>,
+        max_len_payout: Option<u32>,
+    ) -> Option<Payout> {
+        assert_one_yocto();
+        self.assert_transfers_not_paused();
+        self.assert_payouts_not_paused();
+
+        let sender_id = env::predecessor_account_id();
+        let previous_owner_id = self.tokens.owner_by_id.get(&token_id).expect("no token");
+
+        // Payout calculation, a bad royalty setup fails here before anything is transferred
+        let payout = balance
+            .map(|balance| {
+                self.internal_compute_payout(
+                    &token_id,
+                    &previous_owner_id,
+                    balance.into(),
+                    max_len_payout,
+                )
+            })
+            .transpose()
+            .unwrap_or_else(|err| env::panic(err.as_bytes()));
+
+        // Transfer
+        self.tokens
+            .nft_transfer(receiver_id.clone(), token_id.clone(), approval_id, None);
+
+        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
+            Some(sender_id)
+        } else {
+            None
+        };
+
+        //insert seller to seller_by_id
+        let count_sell = if let Some(count) = self.seller_by_id.get(&previous_owner_id) {
+            count + 1
+        } else {
+            1
+        };
+
+        self.seller_by_id.insert(&previous_owner_id, &count_sell);
+
+        self.events.nft_transfer(
+            previous_owner_id,
+            receiver_id.to_string(),
+            vec![token_id],
+            None,
+            authorized_id,
+        );
+
+        payout
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.tokens.owner_id.clone()
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner_id.clone()
+    }
+
+    #[payable]
+    pub fn propose_owner(&mut self, new_owner_id: ValidAccountId) {
+        assert_one_yocto();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.tokens.owner_id,
+            "Paras: Owner only"
+        );
+        let new_owner_id: AccountId = new_owner_id.into();
+        assert_ne!(
+            new_owner_id, self.tokens.owner_id,
+            "Paras: already the owner"
+        );
+        self.pending_owner_id = Some(new_owner_id.clone());
+
+        NearEvent::paras_admin(ParasAdminEventKind::ProposeOwner(vec![
+            event::OwnerProposalData {
+                owner_id: self.tokens.owner_id.clone(),
+                pending_owner_id: new_owner_id,
+            },
+        ]))
+        .log();
+    }
+
+    #[payable]
+    pub fn cancel_owner_proposal(&mut self) {
+        assert_one_yocto();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.tokens.owner_id,
+            "Paras: Owner only"
+        );
+        let pending_owner_id = self
+            .pending_owner_id
+            .take()
+            .expect("Paras: no pending owner");
+
+        NearEvent::paras_admin(ParasAdminEventKind::CancelOwnerProposal(vec![
+            event::OwnerProposalData {
+                owner_id: self.tokens.owner_id.clone(),
+                pending_owner_id,
+            },
+        ]))
+        .log();
+    }
+
+    #[payable]
+    pub fn accept_ownership(&mut self) {
+        assert_one_yocto();
+        let caller = env::predecessor_account_id();
+        assert_eq!(
+            Some(&caller),
+            self.pending_owner_id.as_ref(),
+            "Paras: Pending owner only"
+        );
+        let old_owner_id = std::mem::replace(&mut self.tokens.owner_id, caller.clone());
+        self.pending_owner_id = None;
+
+        NearEvent::paras_admin(ParasAdminEventKind::AcceptOwnership(vec![
+            event::AcceptOwnershipData {
+                old_owner_id,
+                owner_id: caller,
+            },
+        ]))
+        .log();
+    }
+}
+
+// near_contract_standards::impl_non_fungible_token_core!(Contract, tokens);
+// near_contract_standards::impl_non_fungible_token_enumeration!(Contract, tokens);
+near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens);
+
+#[near_bindgen]
+impl NonFungibleTokenMetadataProvider for Contract {
+    fn nft_metadata(&self) -> NFTContractMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[near_bindgen]
+impl NonFungibleTokenResolver for Contract {
+    #[private]
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        let resp: bool = self.tokens.nft_resolve_transfer(
+            previous_owner_id.clone(),
+            receiver_id.clone(),
+            token_id.clone(),
+            approved_account_ids,
+        );
+
+        // if not successful, return nft back to original owner
+        if !resp {
+            self.events
+                .nft_transfer(receiver_id, previous_owner_id, vec![token_id], None, None);
+        }
+
+        resp
+    }
+}
+
+/// from https://github.com/near/near-sdk-rs/blob/e4abb739ff953b06d718037aa1b8ab768db17348/near-contract-standards/src/non_fungible_token/utils.rs#L29
+
+fn refund_deposit(storage_used: u64, extra_spend: Balance) {
+    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
+    let attached_deposit = env::attached_deposit() - extra_spend;
+
+    assert!(
+        required_cost <= attached_deposit,
+        "Must attach {} yoctoNEAR to cover storage",
+        required_cost,
+    );
+
+    let refund = attached_deposit - required_cost;
+    if refund > 1 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
+    }
+}
+
+/// Fields set in `token_override` replace the ones in `token_metadata`
+fn merge_token_metadata(token_metadata: &mut TokenMetadata, token_override: TokenMetadata) {
+    let TokenMetadata {
+        title,
+        description,
+        media,
+        media_hash,
+        copies,
+        issued_at,
+        expires_at,
+        starts_at,
+        updated_at,
+        extra,
+        reference,
+        reference_hash,
+    } = token_override;
+    token_metadata.title = title.or(token_metadata.title.take());
+    token_metadata.description = description.or(token_metadata.description.take());
+    token_metadata.media = media.or(token_metadata.media.take());
+    token_metadata.media_hash = media_hash.or(token_metadata.media_hash.take());
+    token_metadata.copies = copies.or(token_metadata.copies);
+    token_metadata.issued_at = issued_at.or(token_metadata.issued_at.take());
+    token_metadata.expires_at = expires_at.or(token_metadata.expires_at.take());
+    token_metadata.starts_at = starts_at.or(token_metadata.starts_at.take());
+    token_metadata.updated_at = updated_at.or(token_metadata.updated_at.take());
+    token_metadata.extra = extra.or(token_metadata.extra.take());
+    token_metadata.reference = reference.or(token_metadata.reference.take());
+    token_metadata.reference_hash = reference_hash.or(token_metadata.reference_hash.take());
+}
+
+fn to_sec(timestamp: Timestamp) -> TimestampSec {
+    (timestamp / 10u64.pow(9)) as u32
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    const ONE_NEAR: Balance = 10u128.pow(24);
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(5))
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn contract_metadata() -> NFTContractMetadata {
+        NFTContractMetadata {
+            spec: NFT_METADATA_SPEC.to_string(),
+            name: "Fella Blooms".to_string(),
+            symbol: "FELLA".to_string(),
+            icon: None,
+            base_uri: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    fn token_metadata(title: &str, copies: Option<u64>) -> TokenMetadata {
+        TokenMetadata {
+            title: Some(title.to_string()),
+            description: None,
+            media: Some(format!("{}.png", title)),
+            media_hash: None,
+            copies,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    fn token_series(token_series_id: &str) -> TokenSeries {
+        TokenSeries {
+            metadata: token_metadata(token_series_id, Some(10)),
+            creator_id: accounts(2).to_string(),
+            tokens: UnorderedSet::new(format!("t{}", token_series_id).into_bytes()),
+            price: None,
+            is_mintable: true,
+            royalty: HashMap::new(),
+        }
+    }
+
+    fn non_fungible_token() -> NonFungibleToken {
+        NonFungibleToken::new(
+            StorageKey::NonFungibleToken,
+            accounts(0),
+            Some(StorageKey::TokenMetadata),
+            Some(StorageKey::Enumeration),
+            Some(StorageKey::Approval),
+        )
+    }
+
+    fn transaction_fee() -> TransactionFee {
+        TransactionFee {
+            next_fee: None,
+            start_time: None,
+            current_fee: 500,
+        }
+    }
+
+    fn setup_contract() -> Contract {
+        testing_env!(get_context(accounts(0)).build());
+        Contract::new_default_meta(accounts(0), accounts(4), "whitelist.near".to_string(), 2)
+    }
+
+    /// Makes `predecessor_account_id` the caller of what comes next
+    fn set_caller(predecessor_account_id: ValidAccountId, attached_deposit: Balance) {
+        testing_env!(get_context(predecessor_account_id)
+            .attached_deposit(attached_deposit)
+            .build());
+    }
+
+    /// Owner creates a series titled `title`, returns its id
+    fn create_series(contract: &mut Contract, title: &str, copies: Option<u64>) -> TokenSeriesId {
+        set_caller(accounts(0), ONE_NEAR);
+        contract
+            .nft_create_series(None, token_metadata(title, copies), None, None)
+            .token_series_id
+    }
+
+    /// Stands in for `count` minted editions, without going through a mint
+    fn add_editions(contract: &mut Contract, token_series_id: &TokenSeriesId, count: u64) {
+        let mut token_series = contract.token_series_by_id.get(token_series_id).unwrap();
+        for edition in 1..=count {
+            token_series.tokens.insert(&format!(
+                "{}{}{}",
+                token_series_id, TOKEN_DELIMETER, edition
+            ));
+        }
+        contract
+            .token_series_by_id
+            .insert(token_series_id, &token_series);
+    }
+
+    fn metadata_update_token_ids() -> Vec<TokenId> {
+        get_logs()
+            .iter()
+            .filter_map(|log| NearEvent::from_log_line(log))
+            .flat_map(|event| match event {
+                NearEvent::Nep171(event::Nep171Event {
+                    event_kind: event::Nep171EventKind::NftMetadataUpdate(data),
+                    ..
+                }) => data
+                    .into_iter()
+                    .flat_map(|update| update.token_ids)
+                    .collect::<Vec<_>>(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn migrate_from_v1() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut token_series_by_id = UnorderedMap::new(StorageKey::TokenSeriesById);
+        for token_series_id in ["1", "2"].iter() {
+            token_series_by_id.insert(&token_series_id.to_string(), &token_series(token_series_id));
+        }
+        env::state_write(&ContractV1 {
+            tokens: non_fungible_token(),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&contract_metadata())),
+            token_series_by_id,
+            treasury_id: accounts(4).to_string(),
+            transaction_fee: transaction_fee(),
+        });
+
+        let contract = Contract::migrate(None);
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(contract.get_owner(), accounts(0).to_string());
+        assert_eq!(contract.get_raffle_length(), 2);
+        assert!(contract.whitelist_contract_id.is_none());
+        assert_eq!(contract.nft_metadata().symbol, "FELLA");
+        assert!(get_logs()[0].contains(r#""from_version":1"#));
+    }
+
+    #[test]
+    fn migrate_from_v2_keeps_og_entries() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut account_id_og: HashMap<AccountId, u32> = HashMap::new();
+        account_id_og.insert(accounts(1).to_string(), 2);
+        env::state_write(&ContractV2 {
+            tokens: non_fungible_token(),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&contract_metadata())),
+            token_series_by_id: UnorderedMap::new(StorageKey::TokenSeriesById),
+            seller_by_id: UnorderedMap::new(StorageKey::SellerById),
+            raffle: RaffleV1 {
+                len: 2,
+                prefix: StorageKey::Raffle.try_to_vec().unwrap(),
+            },
+            token_series_id_minted: 0,
+            treasury_id: accounts(4).to_string(),
+            whitelist_contract_id: "whitelist.near".to_string(),
+            transaction_fee: transaction_fee(),
+            account_id_og,
+            balance_mint_og: 1,
+            market_data_transaction_fee: MarketDataTransactionFee {
+                transaction_fee: UnorderedMap::new(StorageKey::MarketDataTransactionFee),
+            },
+        });
+
+        let mut contract = Contract::migrate(Some(2));
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(contract.get_og_balance(&accounts(1).to_string()), 2);
+        assert_eq!(contract.get_balance_mint_og(), 1);
+        assert!(get_logs()[0].contains(r#""from_version":2"#));
+
+        // the migrated pool gets indexed before series can be reserved
+        set_caller(accounts(0), ONE_NEAR);
+        assert_eq!(contract.raffle_index_positions(10).0, 0);
+        set_caller(accounts(0), 1);
+        contract.raffle_reserve_series(vec!["1".to_string()]);
+        assert_eq!(
+            contract.get_raffle_series(None, None),
+            vec!["2".to_string()]
+        );
+    }
+
+    #[test]
+    fn migrate_from_v2_after_non_raffle_mint() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut token_series_by_id = UnorderedMap::new(StorageKey::TokenSeriesById);
+        for token_series_id in ["1", "2", "3"].iter() {
+            token_series_by_id.insert(&token_series_id.to_string(), &token_series(token_series_id));
+        }
+        // series 2 was drawn out of a raffle of 2, series 3 was minted by its creator
+        env::state_write(&ContractV2 {
+            tokens: non_fungible_token(),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&contract_metadata())),
+            token_series_by_id,
+            seller_by_id: UnorderedMap::new(StorageKey::SellerById),
+            raffle: RaffleV1 {
+                len: 1,
+                prefix: StorageKey::Raffle.try_to_vec().unwrap(),
+            },
+            token_series_id_minted: 2,
+            treasury_id: accounts(4).to_string(),
+            whitelist_contract_id: "whitelist.near".to_string(),
+            transaction_fee: transaction_fee(),
+            account_id_og: HashMap::new(),
+            balance_mint_og: 1,
+            market_data_transaction_fee: MarketDataTransactionFee {
+                transaction_fee: UnorderedMap::new(StorageKey::MarketDataTransactionFee),
+            },
+        });
+
+        let mut contract = Contract::migrate(Some(2));
+        assert_eq!(contract.get_raffle_length(), 1);
+
+        set_caller(accounts(2), 1);
+        assert_eq!(
+            contract.nft_set_series_price("3".to_string(), Some(U128(ONE_NEAR))),
+            Some(U128(ONE_NEAR))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: Owner only")]
+    fn migrate_by_non_owner() {
+        testing_env!(get_context(accounts(1)).build());
+        env::state_write(&ContractV1 {
+            tokens: non_fungible_token(),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&contract_metadata())),
+            token_series_by_id: UnorderedMap::new(StorageKey::TokenSeriesById),
+            treasury_id: accounts(4).to_string(),
+            transaction_fee: transaction_fee(),
+        });
+        Contract::migrate(None);
+    }
+
+    #[test]
+    fn admin_grants_every_role_except_admin() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.grant_role(accounts(1), Role::Admin);
+
+        set_caller(accounts(1), 1);
+        let roles = [
+            Role::Minter,
+            Role::FeeManager,
+            Role::AllowlistManager,
+            Role::Pauser,
+        ];
+        for role in roles.iter() {
+            contract.grant_role(accounts(2), *role);
+            assert!(contract.has_role(accounts(2).to_string(), *role));
+        }
+        assert!(!contract.has_role(accounts(2).to_string(), Role::Admin));
+        assert_eq!(contract.get_roles(accounts(2).to_string()), roles.to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: Owner only")]
+    fn admin_cannot_grant_admin() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.grant_role(accounts(1), Role::Admin);
+
+        set_caller(accounts(1), 1);
+        contract.grant_role(accounts(2), Role::Admin);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: Admin role required")]
+    fn non_admin_cannot_grant_roles() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.grant_role(accounts(1), Role::Minter);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: FeeManager role required")]
+    fn revoked_role_loses_access() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.grant_role(accounts(1), Role::FeeManager);
+
+        set_caller(accounts(1), 1);
+        contract.set_transaction_fee(100, None);
+
+        set_caller(accounts(0), 1);
+        contract.revoke_role(accounts(1), Role::FeeManager);
+        assert!(!contract.has_role(accounts(1).to_string(), Role::FeeManager));
+
+        set_caller(accounts(1), 1);
+        contract.set_transaction_fee(200, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: FeeManager role required")]
+    fn set_transaction_fee_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.set_transaction_fee(100, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: AllowlistManager role required")]
+    fn set_balance_mint_og_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.set_balance_mint_og(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: AllowlistManager role required")]
+    fn add_og_account_id_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.add_og_account_id(accounts(1).to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: AllowlistManager role required")]
+    fn remove_og_account_id_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.remove_og_account_id(accounts(2).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: Admin role required")]
+    fn set_treasury_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.set_treasury(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: Minter role required")]
+    fn nft_create_series_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), ONE_NEAR);
+        contract.nft_create_series(None, token_metadata("A", Some(1)), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: Minter role required")]
+    fn nft_create_series_custom_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), ONE_NEAR);
+        contract.nft_create_series_custom(
+            "9".to_string(),
+            None,
+            token_metadata("A", Some(1)),
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    fn update_contract_metadata() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_update_contract_metadata(
+            Some("https://example.com/".to_string()),
+            None,
+            None,
+            None,
+        );
+
+        assert_eq!(
+            contract.nft_metadata().base_uri,
+            Some("https://example.com/".to_string())
+        );
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"contract_metadata_update""#)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: Owner only")]
+    fn update_contract_metadata_by_non_owner() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), ONE_NEAR);
+        contract.nft_update_contract_metadata(None, None, None, None);
+    }
+
+    #[test]
+    fn update_series_metadata_logs_minted_editions() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "A", Some(10));
+        add_editions(&mut contract, &token_series_id, 2);
+
+        let mut metadata = token_metadata("A", Some(10));
+        metadata.description = Some("updated".to_string());
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_update_series_metadata(token_series_id.clone(), metadata);
+
+        assert_eq!(
+            contract
+                .nft_get_series_single(token_series_id)
+                .metadata
+                .description,
+            Some("updated".to_string())
+        );
+        assert_eq!(
+            metadata_update_token_ids(),
+            vec!["1:1".to_string(), "1:2".to_string()]
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Paras: only description, media and reference can change after the first mint"
+    )]
+    fn update_series_title_after_mint() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "A", Some(10));
+        add_editions(&mut contract, &token_series_id, 1);
+
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_update_series_metadata(token_series_id, token_metadata("B", Some(10)));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Paras: media, media_hash and extra are frozen once the drop has started minting"
+    )]
+    fn update_unminted_series_media_mid_drop() {
+        let mut contract = setup_contract();
+        let minted_series_id = create_series(&mut contract, "A", Some(10));
+        let token_series_id = create_series(&mut contract, "B", Some(10));
+        set_caller(accounts(0), 1);
+        contract.set_provenance_hash(env::sha256(b"provenance").into());
+        mint_edition(&mut contract, &minted_series_id, accounts(1));
+
+        let mut metadata = token_metadata("B", Some(10));
+        metadata.media = Some("C.png".to_string());
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_update_series_metadata(token_series_id, metadata);
+    }
+
+    #[test]
+    fn update_large_series_metadata_logs_editions_by_page() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "A", None);
+        add_editions(
+            &mut contract,
+            &token_series_id,
+            MAX_METADATA_UPDATE_TOKEN_IDS + 1,
+        );
+
+        let mut metadata = token_metadata("A", None);
+        metadata.description = Some("updated".to_string());
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_update_series_metadata(token_series_id.clone(), metadata);
+        assert!(metadata_update_token_ids().is_empty());
+
+        set_caller(accounts(0), 1);
+        contract.nft_log_series_metadata_update(token_series_id.clone(), None, None);
+        assert_eq!(
+            metadata_update_token_ids().len() as u64,
+            MAX_METADATA_UPDATE_TOKEN_IDS
+        );
+
+        set_caller(accounts(0), 1);
+        contract.nft_log_series_metadata_update(
+            token_series_id,
+            Some(U128(MAX_METADATA_UPDATE_TOKEN_IDS as u128)),
+            None,
+        );
+        assert_eq!(metadata_update_token_ids(), vec!["1:301".to_string()]);
+    }
+
+    /// Owner creates a series whose media_hash is sha256 of `title`, returns the hash
+    fn create_hashed_series(contract: &mut Contract, title: &str) -> Vec<u8> {
+        let media_hash = env::sha256(title.as_bytes());
+        let mut metadata = token_metadata(title, Some(10));
+        metadata.media_hash = Some(media_hash.clone().into());
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_create_series(None, metadata, None, None);
+        media_hash
+    }
+
+    #[test]
+    fn verify_provenance_of_hashed_series() {
+        let mut contract = setup_contract();
+        let media_hashes = [
+            create_hashed_series(&mut contract, "Rose"),
+            create_hashed_series(&mut contract, "Tulip"),
+        ]
+        .concat();
+        assert!(!contract.verify_provenance());
+
+        set_caller(accounts(0), 1);
+        contract.set_provenance_hash(env::sha256(&media_hashes).into());
+        assert!(contract.verify_provenance());
+
+        contract.set_provenance_hash(env::sha256(b"other").into());
+        assert!(!contract.verify_provenance());
+    }
+
+    #[test]
+    fn verify_provenance_with_series_without_media_hash() {
+        let mut contract = setup_contract();
+        let media_hash = create_hashed_series(&mut contract, "Rose");
+        set_caller(accounts(0), 1);
+        contract.set_provenance_hash(env::sha256(&media_hash).into());
+        create_series(&mut contract, "Tulip", Some(10));
+
+        assert!(!contract.verify_provenance());
+    }
+
+    /// Creator of `token_series_id` mints an edition to `receiver_id`
+    fn mint_edition(
+        contract: &mut Contract,
+        token_series_id: &TokenSeriesId,
+        receiver_id: ValidAccountId,
+    ) -> TokenId {
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_mint_creator(token_series_id.clone(), receiver_id)
+    }
+
+    fn token_override(title: &str) -> TokenMetadata {
+        let mut metadata = token_metadata(title, None);
+        metadata.media = None;
+        metadata
+    }
+
+    #[test]
+    fn set_token_metadata_overrides_title() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_set_token_metadata(token_id.clone(), Some(token_override("Golden Rose")));
+
+        let metadata = contract.nft_token(token_id).unwrap().metadata.unwrap();
+        assert_eq!(metadata.title, Some("Golden Rose".to_string()));
+        assert_eq!(metadata.media, Some("Rose.png".to_string()));
+    }
+
+    #[test]
+    fn set_token_metadata_overrides_media() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        let mut metadata = token_metadata("Golden Rose", None);
+        metadata.media_hash = Some(env::sha256(b"Golden Rose.png").into());
+
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_set_token_metadata(token_id.clone(), Some(metadata));
+
+        let metadata = contract.nft_token(token_id).unwrap().metadata.unwrap();
+        assert_eq!(metadata.media, Some("Golden Rose.png".to_string()));
+        assert_eq!(
+            metadata.media_hash,
+            Some(env::sha256(b"Golden Rose.png").into())
         );
-        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
-        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+    }
 
-        self.token_series_by_id
-            .iter()
-            .skip(start_index as usize)
-            .take(limit)
-            .map(|(token_series_id, token_series)| TokenSeriesJson {
-                token_series_id,
-                metadata: token_series.metadata,
-                creator_id: token_series.creator_id,
-                royalty: token_series.royalty,
-                transaction_fee: None,
-            })
-            .collect()
+    #[test]
+    #[should_panic(expected = "Paras: media and media_hash are overridden together")]
+    fn set_token_metadata_media_without_media_hash() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_set_token_metadata(token_id, Some(token_metadata("Tulip", None)));
     }
 
-    pub fn nft_supply_for_series(&self, token_series_id: TokenSeriesId) -> U64 {
-        self.token_series_by_id
-            .get(&token_series_id)
-            .expect("Token series not exist")
-            .tokens
-            .len()
-            .into()
+    #[test]
+    #[should_panic(expected = "Paras: media is frozen while hidden or under a provenance hash")]
+    fn set_token_metadata_media_under_provenance_hash() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        set_caller(accounts(0), 1);
+        contract.set_provenance_hash(env::sha256(b"provenance").into());
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        let mut metadata = token_metadata("Tulip", None);
+        metadata.media_hash = Some(env::sha256(b"Tulip.png").into());
+
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_set_token_metadata(token_id, Some(metadata));
     }
 
-    pub fn nft_tokens_by_series(
-        &self,
-        token_series_id: TokenSeriesId,
-        from_index: Option<U128>,
-        limit: Option<u64>,
-    ) -> Vec<Token> {
-        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
-        let tokens = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .unwrap()
-            .tokens;
-        assert!(
-            (tokens.len() as u128) > start_index,
-            "Out of bounds, please use a smaller from_index."
-        );
-        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
-        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+    #[test]
+    #[should_panic(expected = "Paras: copies always come from the series")]
+    fn set_token_metadata_copies_is_frozen() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        let mut metadata = token_override("Rose");
+        metadata.copies = Some(1);
+
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_set_token_metadata(token_id, Some(metadata));
+    }
 
-        tokens
-            .iter()
-            .skip(start_index as usize)
-            .take(limit)
-            .map(|token_id| self.nft_token(token_id).unwrap())
-            .collect()
+    #[test]
+    fn burn_removes_token_metadata_override() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_set_token_metadata(token_id.clone(), Some(token_override("Golden Rose")));
+
+        set_caller(accounts(1), 1);
+        contract.nft_burn(token_id.clone());
+
+        assert!(contract.token_metadata_overrides.get(&token_id).is_none());
     }
 
-    pub fn nft_token(&self, token_id: TokenId) -> Option<Token> {
-        let owner_id = self.tokens.owner_by_id.get(&token_id)?;
-        let approved_account_ids = self
-            .tokens
-            .approvals_by_id
-            .as_ref()
-            .and_then(|by_id| by_id.get(&token_id).or_else(|| Some(HashMap::new())));
+    #[test]
+    #[should_panic(expected = "Paras: token series 1 is sold through the raffle")]
+    fn create_raffle_series_with_price() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_create_series(
+            None,
+            token_metadata("Rose", Some(10)),
+            Some(U128(ONE_NEAR)),
+            None,
+        );
+    }
 
-        // CUSTOM (switch metadata for the token_series metadata)
-        let mut token_id_iter = token_id.split(TOKEN_DELIMETER);
-        let token_series_id = token_id_iter.next().unwrap().parse().unwrap();
-        let series_metadata = self
+    #[test]
+    #[should_panic(expected = "Paras: token series 1 is sold through the raffle")]
+    fn set_raffle_series_price() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        set_caller(accounts(0), 1);
+        contract.nft_set_series_price(token_series_id, Some(U128(ONE_NEAR)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: token series 1 is sold through the raffle")]
+    fn buy_raffle_series() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        // a price stored before raffle series were kept from carrying one
+        let mut token_series = contract.token_series_by_id.get(&token_series_id).unwrap();
+        token_series.price = Some(ONE_NEAR);
+        contract
             .token_series_by_id
-            .get(&token_series_id)
-            .unwrap()
-            .metadata;
+            .insert(&token_series_id, &token_series);
 
-        let mut token_metadata = self
-            .tokens
-            .token_metadata_by_id
-            .as_ref()
-            .unwrap()
-            .get(&token_id)
-            .unwrap();
+        set_caller(accounts(1), 2 * ONE_NEAR);
+        contract.nft_buy(token_series_id, accounts(1));
+    }
 
-        // token_metadata.title = Some(format!(
-        //     "{}{}{}",
-        //     series_metadata.title.unwrap(),
-        //     TITLE_DELIMETER,
-        //     token_id_iter.next().unwrap()
-        // ));
+    #[test]
+    #[should_panic(
+        expected = "Paras: token series 3 has a price, raffle series are sold by the draws"
+    )]
+    fn add_priced_series_to_raffle() {
+        let mut contract = setup_contract();
+        create_series(&mut contract, "Rose", Some(10));
+        create_series(&mut contract, "Tulip", Some(10));
+        set_caller(accounts(0), ONE_NEAR);
+        let token_series_id = contract
+            .nft_create_series(
+                None,
+                token_metadata("Lily", Some(10)),
+                Some(U128(ONE_NEAR)),
+                None,
+            )
+            .token_series_id;
+
+        contract.raffle_add_series(vec![token_series_id]);
+    }
 
-        token_metadata.title = Some(format!("{}", series_metadata.title.unwrap(),));
+    #[test]
+    fn weighted_draws_mint_out_series() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(2));
+        create_series(&mut contract, "Tulip", Some(10));
+        set_caller(accounts(0), 1);
+        contract.raffle_reserve_series(vec!["2".to_string()]);
+        contract.set_raffle_mode(RaffleMode::Weighted);
+
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
+        assert_eq!(contract.get_raffle_length(), 1);
+        assert_eq!(contract.get_raffle_weight(token_series_id.clone()).0, 1);
+
+        set_caller(accounts(2), ONE_NEAR);
+        contract.draw_and_mint(accounts(2), None);
+        assert_eq!(contract.get_raffle_length(), 0);
+        assert_eq!(contract.get_raffle_weight(token_series_id).0, 0);
+    }
 
-        token_metadata.reference = series_metadata.reference;
-        token_metadata.media = series_metadata.media;
-        token_metadata.copies = series_metadata.copies;
-        token_metadata.extra = series_metadata.extra;
+    #[test]
+    fn weighted_pool_drops_series_minted_out_by_creator() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(1));
+        create_series(&mut contract, "Tulip", Some(10));
+        set_caller(accounts(0), 1);
+        contract.set_raffle_mode(RaffleMode::Weighted);
+
+        set_caller(accounts(0), ONE_NEAR);
+        contract.nft_mint_creator(token_series_id, accounts(1));
+        assert_eq!(contract.get_raffle_length(), 1);
+        assert_eq!(
+            contract.get_raffle_series(None, None),
+            vec!["2".to_string()]
+        );
+    }
 
-        Some(Token {
-            token_id,
-            owner_id,
-            metadata: Some(token_metadata),
-            approved_account_ids,
-        })
+    fn sale_phase(phase_id: &str, price: Balance, eligibility: PhaseEligibility) -> SalePhase {
+        SalePhase {
+            phase_id: phase_id.to_string(),
+            start_time: 0,
+            end_time: 1_000,
+            price: U128(price),
+            limit_per_account: None,
+            eligibility,
+        }
     }
 
-    pub fn is_seller(&self, account_id: AccountId) -> bool {
-        let count_sell = if let Some(count) = self.seller_by_id.get(&account_id) {
-            count
-        } else {
-            0
+    fn setup_mint_price(contract: &mut Contract) {
+        set_caller(accounts(0), 1);
+        contract.set_mint_price(U128(ONE_NEAR), U128(2 * ONE_NEAR));
+    }
+
+    #[test]
+    fn mint_price_for_og_and_public_accounts() {
+        let mut contract = setup_contract();
+        setup_mint_price(&mut contract);
+        contract.add_og_account_id(accounts(1).to_string(), None);
+
+        let price = |contract: &Contract, account_id: ValidAccountId| {
+            contract
+                .get_mint_price_for_account(account_id.to_string(), None)
+                .unwrap()
+                .0
         };
+        assert_eq!(price(&contract, accounts(1)), ONE_NEAR);
+        assert_eq!(price(&contract, accounts(2)), 2 * ONE_NEAR);
+    }
 
-        count_sell > 0
+    #[test]
+    fn mint_price_for_merkle_og() {
+        let mut contract = setup_contract();
+        setup_mint_price(&mut contract);
+        let leaf = merkle::leaf_hash(&accounts(2).to_string(), 3);
+        contract.set_og_merkle_root(Some(leaf.into()));
+
+        let merkle_proof = MerkleProof {
+            allowance: 3,
+            proof: vec![],
+        };
+        assert_eq!(
+            contract
+                .get_mint_price_for_account(accounts(2).to_string(), Some(merkle_proof))
+                .unwrap()
+                .0,
+            ONE_NEAR
+        );
+        assert_eq!(
+            contract
+                .get_mint_price_for_account(accounts(2).to_string(), None)
+                .unwrap()
+                .0,
+            2 * ONE_NEAR
+        );
     }
 
-    pub fn get_seller(&self) -> Vec<String> {
-        let mut tmp: Vec<String> = vec![];
-        for i in self.seller_by_id.keys() {
-            tmp.push(i);
+    #[test]
+    fn mint_price_in_active_phase() {
+        let mut contract = setup_contract();
+        setup_mint_price(&mut contract);
+        contract.add_og_account_id(accounts(1).to_string(), None);
+        contract.set_sale_phases(vec![sale_phase("og", 3 * ONE_NEAR, PhaseEligibility::Og)]);
+
+        for account_id in [accounts(1), accounts(2)].iter() {
+            assert_eq!(
+                contract
+                    .get_mint_price_for_account(account_id.to_string(), None)
+                    .unwrap()
+                    .0,
+                3 * ONE_NEAR
+            );
         }
-        tmp
     }
 
-    // CUSTOM core standard repeated here because no macro below
+    #[test]
+    fn mint_price_before_next_phase() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        let mut phase = sale_phase("public", ONE_NEAR, PhaseEligibility::Public);
+        phase.start_time = 500;
+        contract.set_sale_phases(vec![phase]);
 
-    pub fn nft_transfer_unsafe(
-        &mut self,
-        receiver_id: ValidAccountId,
-        token_id: TokenId,
-        approval_id: Option<u64>,
-        memo: Option<String>,
-    ) {
-        let sender_id = env::predecessor_account_id();
-        let receiver_id_str = receiver_id.to_string();
-        let (previous_owner_id, _) = self.tokens.internal_transfer(
-            &sender_id,
-            &receiver_id_str,
-            &token_id,
-            approval_id,
-            memo.clone(),
+        assert_eq!(
+            contract.get_mint_price_for_account(accounts(1).to_string(), None),
+            Some(U128(ONE_NEAR))
         );
+    }
 
-        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
-            Some(sender_id)
-        } else {
-            None
-        };
+    fn commit_secret(contract: &mut Contract, secret: &[u8]) {
+        set_caller(accounts(1), ONE_NEAR);
+        contract.commit_draw(env::sha256(secret).into());
+    }
 
-        NearEvent::log_nft_transfer(
-            previous_owner_id,
-            receiver_id_str,
-            vec![token_id],
-            memo,
-            authorized_id,
-        );
+    fn set_reveal_block(block_index: u64) {
+        testing_env!(get_context(accounts(1))
+            .block_index(block_index)
+            .attached_deposit(ONE_NEAR)
+            .build());
     }
 
-    #[payable]
-    pub fn nft_transfer(
-        &mut self,
-        receiver_id: ValidAccountId,
-        token_id: TokenId,
-        approval_id: Option<u64>,
-        memo: Option<String>,
-    ) {
-        let sender_id = env::predecessor_account_id();
-        let previous_owner_id = self
-            .tokens
-            .owner_by_id
-            .get(&token_id)
-            .expect("Token not found");
-        let receiver_id_str = receiver_id.to_string();
-        self.tokens
-            .nft_transfer(receiver_id, token_id.clone(), approval_id, memo.clone());
+    #[test]
+    fn reveal_and_mint_after_commit() {
+        let mut contract = setup_contract();
+        create_series(&mut contract, "Rose", Some(1));
+        create_series(&mut contract, "Tulip", Some(1));
+        commit_secret(&mut contract, b"secret");
+        assert_eq!(
+            contract.get_draw_commitment_block(accounts(1).to_string()),
+            Some(U64(0))
+        );
 
-        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
-            Some(sender_id)
-        } else {
-            None
-        };
+        set_reveal_block(1);
+        let token_id = contract.reveal_and_mint(accounts(1), b"secret".to_vec().into(), None);
 
-        NearEvent::log_nft_transfer(
-            previous_owner_id,
-            receiver_id_str,
-            vec![token_id],
-            memo,
-            authorized_id,
-        );
+        assert!(token_id == "1:1" || token_id == "2:1");
+        assert_eq!(contract.get_raffle_length(), 1);
+        assert!(contract
+            .get_draw_commitment_block(accounts(1).to_string())
+            .is_none());
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"raffle_draw""#)));
     }
 
-    #[payable]
-    pub fn assign_garden(
-        &mut self,
-        receiver_id: ValidAccountId,
-        token_id: TokenId,
-    ) -> PromiseOrValue<bool> {
-        assert_one_yocto();
-        let sender_id = env::predecessor_account_id();
+    #[test]
+    #[should_panic(expected = "Paras: reveal must happen after the commit block")]
+    fn reveal_and_mint_in_commit_block() {
+        let mut contract = setup_contract();
+        create_series(&mut contract, "Rose", Some(1));
+        commit_secret(&mut contract, b"secret");
 
-        if !self.is_seller(sender_id.clone()) {
-            panic!("You must sell at least 1 NFT")
-        }
+        set_reveal_block(0);
+        contract.reveal_and_mint(accounts(1), b"secret".to_vec().into(), None);
+    }
 
-        let (previous_owner_id, old_approvals) =
-            self.tokens
-                .internal_transfer(&sender_id, receiver_id.as_ref(), &token_id, None, None);
+    #[test]
+    #[should_panic(expected = "Paras: secret does not match commitment")]
+    fn reveal_and_mint_with_other_secret() {
+        let mut contract = setup_contract();
+        create_series(&mut contract, "Rose", Some(1));
+        commit_secret(&mut contract, b"secret");
 
-        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
-            Some(sender_id.clone())
-        } else {
-            None
-        };
+        set_reveal_block(1);
+        contract.reveal_and_mint(accounts(1), b"other".to_vec().into(), None);
+    }
 
-        NearEvent::log_nft_transfer(
-            previous_owner_id.clone(),
-            receiver_id.to_string(),
-            vec![token_id.clone()],
-            None,
-            authorized_id,
-        );
+    #[test]
+    #[should_panic(expected = "Paras: no draw commitment")]
+    fn reveal_and_mint_without_commit() {
+        let mut contract = setup_contract();
+        create_series(&mut contract, "Rose", Some(1));
 
-        let msg = if self.is_seller(previous_owner_id.clone()) {
-            "seller".to_string()
-        } else {
-            "not_seller".to_string()
-        };
+        set_reveal_block(1);
+        contract.reveal_and_mint(accounts(1), b"secret".to_vec().into(), None);
+    }
 
-        // Initiating receiver's call and the callback
-        ext_non_fungible_token_receiver::nft_on_transfer(
-            sender_id,
-            previous_owner_id.clone(),
-            token_id.clone(),
-            msg,
-            receiver_id.as_ref(),
-            NO_DEPOSIT,
-            env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
-        )
-        .then(ext_self::nft_resolve_transfer(
-            previous_owner_id,
-            receiver_id.into(),
-            token_id,
-            old_approvals,
-            &env::current_account_id(),
-            NO_DEPOSIT,
-            GAS_FOR_RESOLVE_TRANSFER,
-        ))
-        .into()
+    /// `predecessor_account_id` stages `code` as the raw call input
+    fn stage(contract: &mut Contract, predecessor_account_id: ValidAccountId, code: &[u8]) {
+        let mut context = get_context(predecessor_account_id)
+            .attached_deposit(ONE_NEAR)
+            .build();
+        context.input = code.to_vec();
+        testing_env!(context);
+        contract.stage_code();
     }
 
-    #[payable]
-    pub fn nft_transfer_call(
-        &mut self,
-        receiver_id: ValidAccountId,
-        token_id: TokenId,
-        approval_id: Option<u64>,
-        memo: Option<String>,
-        msg: String,
-    ) -> PromiseOrValue<bool> {
-        assert_one_yocto();
-        let sender_id = env::predecessor_account_id();
-        let (previous_owner_id, old_approvals) = self.tokens.internal_transfer(
-            &sender_id,
-            receiver_id.as_ref(),
-            &token_id,
-            approval_id,
-            memo.clone(),
-        );
+    #[test]
+    fn upgrade_approved_code() {
+        let mut contract = setup_contract();
+        stage(&mut contract, accounts(0), b"new wasm");
+        let code_hash: Base64VecU8 = env::sha256(b"new wasm").into();
+        assert_eq!(contract.get_staged_code_hash(), Some(code_hash.clone()));
+
+        set_caller(accounts(0), 1);
+        contract.approve_code_hash(code_hash.clone());
+        assert_eq!(contract.get_approved_code_hash(), Some(code_hash));
+        contract.upgrade();
+
+        assert!(contract.get_staged_code_hash().is_none());
+        assert!(contract.get_approved_code_hash().is_none());
+        assert!(!env::storage_has_key(
+            &StorageKey::StagedCode.try_to_vec().unwrap()
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: not enough gas for upgrade")]
+    fn upgrade_without_enough_gas() {
+        let mut contract = setup_contract();
+        stage(&mut contract, accounts(0), b"new wasm");
+        set_caller(accounts(0), 1);
+        contract.approve_code_hash(env::sha256(b"new wasm").into());
+
+        testing_env!(get_context(accounts(0))
+            .attached_deposit(1)
+            .prepaid_gas(GAS_FOR_UPGRADE + GAS_FOR_MIGRATE)
+            .build());
+        contract.upgrade();
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: staged code is not approved")]
+    fn upgrade_without_approval() {
+        let mut contract = setup_contract();
+        stage(&mut contract, accounts(0), b"new wasm");
+
+        set_caller(accounts(0), 1);
+        contract.upgrade();
+    }
 
-        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
-            Some(sender_id.clone())
-        } else {
-            None
-        };
+    #[test]
+    #[should_panic(expected = "Paras: staged code is not approved")]
+    fn upgrade_code_staged_after_approval() {
+        let mut contract = setup_contract();
+        stage(&mut contract, accounts(0), b"new wasm");
+        set_caller(accounts(0), 1);
+        contract.approve_code_hash(env::sha256(b"new wasm").into());
+        stage(&mut contract, accounts(0), b"other wasm");
+
+        set_caller(accounts(0), 1);
+        contract.upgrade();
+    }
 
-        NearEvent::log_nft_transfer(
-            previous_owner_id.clone(),
-            receiver_id.to_string(),
-            vec![token_id.clone()],
-            memo,
-            authorized_id,
-        );
+    #[test]
+    #[should_panic(expected = "Paras: Owner only")]
+    fn stage_code_by_non_owner() {
+        let mut contract = setup_contract();
+        stage(&mut contract, accounts(1), b"new wasm");
+    }
 
-        // Initiating receiver's call and the callback
-        ext_non_fungible_token_receiver::nft_on_transfer(
-            sender_id,
-            previous_owner_id.clone(),
-            token_id.clone(),
-            msg,
-            receiver_id.as_ref(),
-            NO_DEPOSIT,
-            env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
-        )
-        .then(ext_self::nft_resolve_transfer(
-            previous_owner_id,
-            receiver_id.into(),
-            token_id,
-            old_approvals,
-            &env::current_account_id(),
-            NO_DEPOSIT,
-            GAS_FOR_RESOLVE_TRANSFER,
-        ))
-        .into()
+    #[test]
+    #[should_panic(expected = "Paras: Owner only")]
+    fn upgrade_by_non_owner() {
+        let mut contract = setup_contract();
+        stage(&mut contract, accounts(0), b"new wasm");
+        set_caller(accounts(0), 1);
+        contract.approve_code_hash(env::sha256(b"new wasm").into());
+
+        set_caller(accounts(1), 1);
+        contract.upgrade();
     }
 
-    // CUSTOM enumeration standard modified here because no macro below
+    #[test]
+    fn accept_proposed_ownership() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.propose_owner(accounts(1));
+        assert_eq!(contract.get_pending_owner(), Some(accounts(1).to_string()));
 
-    pub fn nft_total_supply(&self) -> U128 {
-        (self.tokens.owner_by_id.len() as u128).into()
+        set_caller(accounts(1), 1);
+        contract.accept_ownership();
+
+        assert_eq!(contract.get_owner(), accounts(1).to_string());
+        assert!(contract.get_pending_owner().is_none());
+        // the new owner gets every owner-only method
+        contract.propose_owner(accounts(2));
     }
 
-    pub fn nft_total_series_supply(&self) -> U128 {
-        (self.token_series_by_id.len() as u128).into()
+    #[test]
+    #[should_panic(expected = "Paras: Owner only")]
+    fn previous_owner_loses_access() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.propose_owner(accounts(1));
+        set_caller(accounts(1), 1);
+        contract.accept_ownership();
+
+        set_caller(accounts(0), 1);
+        contract.propose_owner(accounts(2));
     }
 
-    pub fn nft_total_series_minted(&self) -> U128 {
-        (self.token_series_id_minted as u128).into()
+    #[test]
+    #[should_panic(expected = "Paras: Pending owner only")]
+    fn accept_ownership_by_other_account() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.propose_owner(accounts(1));
+
+        set_caller(accounts(2), 1);
+        contract.accept_ownership();
     }
 
-    pub fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
-        // Get starting index, whether or not it was explicitly given.
-        // Defaults to 0 based on the spec:
-        // https://nomicon.io/Standards/NonFungibleToken/Enumeration.html#interface
-        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
-        assert!(
-            (self.tokens.owner_by_id.len() as u128) > start_index,
-            "Out of bounds, please use a smaller from_index."
-        );
-        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
-        assert_ne!(limit, 0, "Cannot provide limit of 0.");
-        self.tokens
-            .owner_by_id
-            .iter()
-            .skip(start_index as usize)
-            .take(limit)
-            .map(|(token_id, _)| self.nft_token(token_id).unwrap())
-            .collect()
+    #[test]
+    #[should_panic(expected = "Paras: Pending owner only")]
+    fn accept_cancelled_ownership() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.propose_owner(accounts(1));
+        contract.cancel_owner_proposal();
+        assert!(contract.get_pending_owner().is_none());
+
+        set_caller(accounts(1), 1);
+        contract.accept_ownership();
     }
 
-    pub fn nft_supply_for_owner(self, account_id: ValidAccountId) -> U128 {
-        let tokens_per_owner = self.tokens.tokens_per_owner.expect(
-            "Could not find tokens_per_owner when calling a method on the enumeration standard.",
-        );
-        tokens_per_owner
-            .get(account_id.as_ref())
-            .map(|account_tokens| U128::from(account_tokens.len() as u128))
-            .unwrap_or(U128(0))
+    #[test]
+    #[should_panic(expected = "Paras: Owner only")]
+    fn propose_owner_by_non_owner() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.propose_owner(accounts(1));
     }
 
-    pub fn nft_tokens_for_owner(
-        &self,
-        account_id: ValidAccountId,
-        from_index: Option<U128>,
-        limit: Option<u64>,
-    ) -> Vec<Token> {
-        let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().expect(
-            "Could not find tokens_per_owner when calling a method on the enumeration standard.",
-        );
-        let token_set = if let Some(token_set) = tokens_per_owner.get(account_id.as_ref()) {
-            token_set
-        } else {
-            return vec![];
-        };
-        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
-        assert_ne!(limit, 0, "Cannot provide limit of 0.");
-        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
-        assert!(
-            token_set.len() as u128 > start_index,
-            "Out of bounds, please use a smaller from_index."
-        );
-        token_set
-            .iter()
-            .skip(start_index as usize)
-            .take(limit)
-            .map(|token_id| self.nft_token(token_id).unwrap())
-            .collect()
+    fn pause(contract: &mut Contract, minting: bool, transfers: bool, payouts: bool) {
+        set_caller(accounts(0), 1);
+        contract.set_pause_state(Some(minting), Some(transfers), Some(payouts));
     }
 
-    pub fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: u32) -> Payout {
-        let owner_id = self.tokens.owner_by_id.get(&token_id).expect("No token id");
-        let mut token_id_iter = token_id.split(TOKEN_DELIMETER);
-        let token_series_id = token_id_iter.next().unwrap().parse().unwrap();
-        let royalty = self
-            .token_series_by_id
-            .get(&token_series_id)
-            .expect("no type")
-            .royalty;
+    #[test]
+    #[should_panic(expected = "Paras: minting is paused")]
+    fn draw_and_mint_while_minting_paused() {
+        let mut contract = setup_contract();
+        create_series(&mut contract, "Rose", Some(1));
+        pause(&mut contract, true, false, false);
 
-        assert!(
-            royalty.len() as u32 <= max_len_payout,
-            "Market cannot payout to that many receivers"
-        );
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
+    }
 
-        let balance_u128: u128 = balance.into();
+    #[test]
+    #[should_panic(expected = "Paras: transfers are paused")]
+    fn transfer_while_transfers_paused() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        pause(&mut contract, false, true, false);
 
-        let mut payout: Payout = Payout {
-            payout: HashMap::new(),
-        };
-        let mut total_perpetual = 0;
+        set_caller(accounts(1), 1);
+        contract.nft_transfer(accounts(2), token_id, None, None);
+    }
 
-        for (k, v) in royalty.iter() {
-            if *k != owner_id {
-                let key = k.clone();
-                payout
-                    .payout
-                    .insert(key, royalty_to_payout(*v, balance_u128));
-                total_perpetual += *v;
-            }
-        }
-        payout.payout.insert(
-            owner_id,
-            royalty_to_payout(10000 - total_perpetual, balance_u128),
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn assign_garden_without_deposit_while_transfers_paused() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        pause(&mut contract, false, true, false);
+
+        set_caller(accounts(1), 0);
+        contract.assign_garden(accounts(2), token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: payouts are paused")]
+    fn transfer_payout_while_payouts_paused() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        pause(&mut contract, false, false, true);
+
+        set_caller(accounts(1), 1);
+        contract.nft_transfer_payout(accounts(2), token_id, None, Some(U128(ONE_NEAR)), Some(10));
+    }
+
+    #[test]
+    fn transfer_after_unpause() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+        pause(&mut contract, true, true, true);
+        pause(&mut contract, false, false, false);
+        assert!(!contract.get_pause_state().transfers);
+
+        set_caller(accounts(1), 1);
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        assert_eq!(
+            contract.nft_token(token_id).unwrap().owner_id,
+            accounts(2).to_string()
         );
-        payout
     }
 
-    #[payable]
-    pub fn nft_transfer_payout(
-        &mut self,
-        receiver_id: ValidAccountId,
-        token_id: TokenId,
-        approval_id: Option<u64>,
-        balance: Option<U128>,
-        max_len_payout: Option<u32>,
-    ) -> Option<Payout> {
-        assert_one_yocto();
+    #[test]
+    fn pauser_sets_pause_state() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), 1);
+        contract.grant_role(accounts(3), Role::Pauser);
 
-        let sender_id = env::predecessor_account_id();
-        // Transfer
-        let previous_token = self.nft_token(token_id.clone()).expect("no token");
-        self.tokens
-            .nft_transfer(receiver_id.clone(), token_id.clone(), approval_id, None);
+        set_caller(accounts(3), 1);
+        contract.set_pause_state(Some(true), None, None);
 
-        // Payout calculation
-        let previous_owner_id = previous_token.owner_id;
-        let mut total_perpetual = 0;
-        let payout = if let Some(balance) = balance {
-            let balance_u128: u128 = u128::from(balance);
-            let mut payout: Payout = Payout {
-                payout: HashMap::new(),
-            };
+        let pause_state = contract.get_pause_state();
+        assert!(pause_state.minting);
+        assert!(!pause_state.transfers);
+        assert!(!pause_state.payouts);
+    }
 
-            let mut token_id_iter = token_id.split(TOKEN_DELIMETER);
-            let token_series_id = token_id_iter.next().unwrap().parse().unwrap();
-            let royalty = self
-                .token_series_by_id
-                .get(&token_series_id)
-                .expect("no type")
-                .royalty;
+    #[test]
+    #[should_panic(expected = "Paras: Pauser role required")]
+    fn set_pause_state_requires_role() {
+        let mut contract = setup_contract();
+        set_caller(accounts(1), 1);
+        contract.set_pause_state(Some(true), None, None);
+    }
 
-            assert!(
-                royalty.len() as u32 <= max_len_payout.unwrap(),
-                "Market cannot payout to that many receivers"
-            );
-            for (k, v) in royalty.iter() {
-                let key = k.clone();
-                if key != previous_owner_id {
-                    payout
-                        .payout
-                        .insert(key, royalty_to_payout(*v, balance_u128));
-                    total_perpetual += *v;
-                }
-            }
+    /// Owner creates the two raffle series and schedules `sale_phases`
+    fn setup_sale(sale_phases: Vec<SalePhase>) -> Contract {
+        let mut contract = setup_contract();
+        create_series(&mut contract, "Rose", Some(10));
+        create_series(&mut contract, "Tulip", Some(10));
+        set_caller(accounts(0), 1);
+        contract.set_sale_phases(sale_phases);
+        contract
+    }
 
-            assert!(total_perpetual <= 10000, "Total payout overflow");
+    #[test]
+    #[should_panic(expected = "Paras: no active sale phase")]
+    fn draw_and_mint_before_first_phase() {
+        let mut phase = sale_phase("public", 0, PhaseEligibility::Public);
+        phase.start_time = 500;
+        let mut contract = setup_sale(vec![phase]);
 
-            payout.payout.insert(
-                previous_owner_id.clone(),
-                royalty_to_payout(10000 - total_perpetual, balance_u128),
-            );
-            Some(payout)
-        } else {
-            None
-        };
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
+    }
 
-        let authorized_id: Option<AccountId> = if sender_id != previous_owner_id {
-            Some(sender_id)
-        } else {
-            None
-        };
+    #[test]
+    #[should_panic(expected = "Paras: phase og is for OG accounts only")]
+    fn draw_and_mint_in_og_phase_by_public_account() {
+        let mut contract = setup_sale(vec![sale_phase("og", 0, PhaseEligibility::Og)]);
 
-        //insert seller to seller_by_id
-        let count_sell = if let Some(count) = self.seller_by_id.get(&previous_owner_id) {
-            count + 1
-        } else {
-            1
-        };
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
+    }
 
-        self.seller_by_id.insert(&previous_owner_id, &count_sell);
+    #[test]
+    fn draw_and_mint_in_og_phase() {
+        let mut contract = setup_sale(vec![sale_phase("og", 0, PhaseEligibility::Og)]);
+        contract.add_og_account_id(accounts(1).to_string(), Some(2));
+        assert_eq!(contract.get_current_phase().unwrap().phase_id, "og");
 
-        NearEvent::log_nft_transfer(
-            previous_owner_id,
-            receiver_id.to_string(),
-            vec![token_id],
-            None,
-            authorized_id,
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
+
+        assert_eq!(contract.get_og_balance(&accounts(1).to_string()), 1);
+        assert_eq!(
+            contract.get_phase_minted("og".to_string(), accounts(1).to_string()),
+            1
         );
+    }
 
-        payout
+    #[test]
+    #[should_panic(
+        expected = "Paras: attached deposit is less than price : 1000000000000000000000000"
+    )]
+    fn draw_and_mint_below_phase_price() {
+        let mut contract = setup_sale(vec![sale_phase(
+            "public",
+            ONE_NEAR,
+            PhaseEligibility::Public,
+        )]);
+
+        set_caller(accounts(1), ONE_NEAR - 1);
+        contract.draw_and_mint(accounts(1), None);
     }
 
-    pub fn get_owner(&self) -> AccountId {
-        self.tokens.owner_id.clone()
+    #[test]
+    #[should_panic(expected = "Paras: phase allowlist is for allowlisted accounts only")]
+    fn draw_and_mint_in_allowlist_phase_without_proof() {
+        let mut contract = setup_sale(vec![sale_phase(
+            "allowlist",
+            0,
+            PhaseEligibility::Allowlist,
+        )]);
+
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
     }
-}
 
-fn royalty_to_payout(a: u32, b: Balance) -> U128 {
-    U128(a as u128 * b / 10_000u128)
-}
+    /// `accounts(1)` draws a token and hands it to `accounts(2)`
+    fn draw_and_give_away(contract: &mut Contract) {
+        set_caller(accounts(1), ONE_NEAR);
+        let token_id = contract.draw_and_mint(accounts(1), None);
+        set_caller(accounts(1), 1);
+        contract.nft_transfer(accounts(2), token_id, None, None);
+    }
 
-// near_contract_standards::impl_non_fungible_token_core!(Contract, tokens);
-// near_contract_standards::impl_non_fungible_token_enumeration!(Contract, tokens);
-near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens);
+    #[test]
+    #[should_panic(expected = "Mint Not Allowed")]
+    fn draw_and_mint_past_max_after_giving_token_away() {
+        let mut contract = setup_sale(vec![]);
+        draw_and_give_away(&mut contract);
+        assert_eq!(contract.get_minted_count(accounts(1).to_string()), 1);
 
-#[near_bindgen]
-impl NonFungibleTokenMetadataProvider for Contract {
-    fn nft_metadata(&self) -> NFTContractMetadata {
-        self.metadata.get().unwrap()
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
     }
-}
 
-#[near_bindgen]
-impl NonFungibleTokenResolver for Contract {
-    #[private]
-    fn nft_resolve_transfer(
-        &mut self,
-        previous_owner_id: AccountId,
-        receiver_id: AccountId,
-        token_id: TokenId,
-        approved_account_ids: Option<HashMap<AccountId, u64>>,
-    ) -> bool {
-        let resp: bool = self.tokens.nft_resolve_transfer(
-            previous_owner_id.clone(),
-            receiver_id.clone(),
-            token_id.clone(),
-            approved_account_ids,
+    #[test]
+    fn draw_and_mint_while_holding_bought_token() {
+        let mut contract = setup_sale(vec![]);
+        draw_and_give_away(&mut contract);
+
+        // holding a token doesn't count, only what the account minted itself
+        set_caller(accounts(2), ONE_NEAR);
+        contract.draw_and_mint(accounts(2), None);
+        assert_eq!(contract.get_minted_count(accounts(2).to_string()), 1);
+    }
+
+    #[test]
+    fn draw_and_mint_up_to_raised_max() {
+        let mut contract = setup_sale(vec![]);
+        set_caller(accounts(0), 1);
+        contract.set_max_mint_per_account(Some(2));
+
+        draw_and_give_away(&mut contract);
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
+        assert_eq!(contract.get_minted_count(accounts(1).to_string()), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: phase public limit of 1 per account reached")]
+    fn draw_and_mint_past_phase_limit() {
+        let mut phase = sale_phase("public", 0, PhaseEligibility::Public);
+        phase.limit_per_account = Some(1);
+        let mut contract = setup_sale(vec![phase]);
+        set_caller(accounts(0), 1);
+        contract.set_max_mint_per_account(None);
+
+        draw_and_give_away(&mut contract);
+        set_caller(accounts(1), ONE_NEAR);
+        contract.draw_and_mint(accounts(1), None);
+    }
+
+    /// Owner hides the collection behind a placeholder and commits to a provenance hash
+    fn hide_collection(contract: &mut Contract) {
+        set_caller(accounts(0), 1);
+        contract.set_provenance_hash(env::sha256(b"provenance").into());
+        set_caller(accounts(0), ONE_NEAR);
+        contract.set_placeholder_metadata(token_metadata("Mystery Bloom", None));
+    }
+
+    #[test]
+    fn placeholder_until_reveal() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        hide_collection(&mut contract);
+        let token_id = mint_edition(&mut contract, &token_series_id, accounts(1));
+
+        let metadata = contract
+            .nft_token(token_id.clone())
+            .unwrap()
+            .metadata
+            .unwrap();
+        assert_eq!(metadata.title, Some("Mystery Bloom".to_string()));
+        assert_eq!(metadata.media, Some("Mystery Bloom.png".to_string()));
+        let series = contract.nft_get_series_single(token_series_id.clone());
+        assert_eq!(series.metadata.title, Some("Mystery Bloom".to_string()));
+        assert!(!contract.is_revealed());
+
+        set_caller(accounts(0), 1);
+        contract.reveal("ipfs://revealed".to_string());
+
+        let metadata = contract.nft_token(token_id).unwrap().metadata.unwrap();
+        assert_eq!(metadata.title, Some("Rose".to_string()));
+        assert_eq!(metadata.media, Some("Rose.png".to_string()));
+        assert_eq!(
+            contract.nft_metadata().base_uri,
+            Some("ipfs://revealed".to_string())
         );
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"reveal""#)));
+    }
 
-        // if not successful, return nft back to original owner
-        if !resp {
-            NearEvent::log_nft_transfer(receiver_id, previous_owner_id, vec![token_id], None, None);
-        }
+    #[test]
+    fn reveal_after_drawing_hidden_series() {
+        let mut contract = setup_sale(vec![]);
+        hide_collection(&mut contract);
+        set_caller(accounts(1), ONE_NEAR);
+        let token_id = contract.draw_and_mint(accounts(1), None);
+        let metadata = contract
+            .nft_token(token_id.clone())
+            .unwrap()
+            .metadata
+            .unwrap();
+        assert_eq!(metadata.title, Some("Mystery Bloom".to_string()));
 
-        resp
+        set_caller(accounts(0), 1);
+        contract.reveal("ipfs://revealed".to_string());
+
+        assert!(contract.is_revealed());
+        let metadata = contract.nft_token(token_id).unwrap().metadata.unwrap();
+        assert_ne!(metadata.title, Some("Mystery Bloom".to_string()));
     }
-}
 
-/// from https://github.com/near/near-sdk-rs/blob/e4abb739ff953b06d718037aa1b8ab768db17348/near-contract-standards/src/non_fungible_token/utils.rs#L29
+    #[test]
+    #[should_panic(expected = "Paras: provenance hash must be set before hiding the collection")]
+    fn hide_collection_without_provenance_hash() {
+        let mut contract = setup_contract();
+        set_caller(accounts(0), ONE_NEAR);
+        contract.set_placeholder_metadata(token_metadata("Mystery Bloom", None));
+    }
 
-fn refund_deposit(storage_used: u64, extra_spend: Balance) {
-    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
-    let attached_deposit = env::attached_deposit() - extra_spend;
+    #[test]
+    #[should_panic(expected = "Paras: already revealed")]
+    fn reveal_twice() {
+        let mut contract = setup_contract();
+        hide_collection(&mut contract);
+        set_caller(accounts(0), 1);
+        contract.reveal("ipfs://revealed".to_string());
 
-    assert!(
-        required_cost <= attached_deposit,
-        "Must attach {} yoctoNEAR to cover storage",
-        required_cost,
-    );
+        contract.reveal("ipfs://other".to_string());
+    }
 
-    let refund = attached_deposit - required_cost;
-    if refund > 1 {
-        Promise::new(env::predecessor_account_id()).transfer(refund);
+    #[test]
+    #[should_panic(expected = "Paras: provenance hash is frozen after the first mint")]
+    fn set_provenance_hash_after_mint() {
+        let mut contract = setup_contract();
+        let token_series_id = create_series(&mut contract, "Rose", Some(10));
+        hide_collection(&mut contract);
+        mint_edition(&mut contract, &token_series_id, accounts(1));
+
+        set_caller(accounts(0), 1);
+        contract.set_provenance_hash(env::sha256(b"other").into());
     }
-}
 
-fn to_sec(timestamp: Timestamp) -> TimestampSec {
-    (timestamp / 10u64.pow(9)) as u32
+    /// Mints an edition of a series paying accounts(0) 10% royalty, approved to accounts(3)
+    fn approve_royalty_token(contract: &mut Contract) -> TokenId {
+        let mut royalty = HashMap::new();
+        royalty.insert(accounts(0).to_string(), 1000);
+        set_caller(accounts(0), ONE_NEAR);
+        let token_series_id = contract
+            .nft_create_series(None, token_metadata("Rose", Some(10)), None, Some(royalty))
+            .token_series_id;
+        let token_id = mint_edition(contract, &token_series_id, accounts(1));
+        set_caller(accounts(1), ONE_NEAR);
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+        token_id
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Paras: approved transfers of tokens with royalty go through nft_transfer_payout"
+    )]
+    fn approved_transfer_of_token_with_royalty() {
+        let mut contract = setup_contract();
+        let token_id = approve_royalty_token(&mut contract);
+
+        set_caller(accounts(3), 1);
+        contract.nft_transfer(accounts(2), token_id, None, None);
+    }
+
+    #[test]
+    fn approved_transfer_payout_without_max_len_payout() {
+        let mut contract = setup_contract();
+        let token_id = approve_royalty_token(&mut contract);
+
+        set_caller(accounts(3), 1);
+        let payout = contract
+            .nft_transfer_payout(
+                accounts(2),
+                token_id.clone(),
+                None,
+                Some(U128(ONE_NEAR)),
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(payout.payout.len(), 2);
+        assert_eq!(payout.payout[&accounts(0).to_string()], U128(ONE_NEAR / 10));
+        assert_eq!(
+            contract.nft_token(token_id).unwrap().owner_id,
+            accounts(2).to_string()
+        );
+    }
 }
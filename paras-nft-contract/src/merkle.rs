@@ -0,0 +1,83 @@
+use near_sdk::env;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+/// Proof that `(account_id, allowance)` is a leaf under the OG merkle root
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MerkleProof {
+    pub allowance: u32,
+    pub proof: Vec<Base64VecU8>,
+}
+
+/// Leaf of the OG merkle tree e.g. sha256("alice.near:3")
+pub fn leaf_hash(account_id: &AccountId, allowance: u32) -> Vec<u8> {
+    env::sha256(format!("{}:{}", account_id, allowance).as_bytes())
+}
+
+/// Each pair is sorted before hashing, so proofs don't carry left/right positions
+pub fn hash_pair(a: &[u8], b: &[u8]) -> Vec<u8> {
+    if a <= b {
+        env::sha256(&[a, b].concat())
+    } else {
+        env::sha256(&[b, a].concat())
+    }
+}
+
+pub fn verify(root: &[u8], leaf: Vec<u8>, proof: &[Base64VecU8]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, &sibling.0));
+    computed == root
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn leaves() -> Vec<Vec<u8>> {
+        vec![
+            leaf_hash(&"alice".to_string(), 3),
+            leaf_hash(&"bob".to_string(), 1),
+            leaf_hash(&"carol".to_string(), 2),
+            leaf_hash(&"dave".to_string(), 5),
+        ]
+    }
+
+    #[test]
+    fn verify_every_leaf() {
+        testing_env!(VMContextBuilder::new().build());
+        let l = leaves();
+        let left = hash_pair(&l[0], &l[1]);
+        let right = hash_pair(&l[2], &l[3]);
+        let root = hash_pair(&left, &right);
+
+        let proofs = vec![
+            vec![l[1].clone(), right.clone()],
+            vec![l[0].clone(), right.clone()],
+            vec![l[3].clone(), left.clone()],
+            vec![l[2].clone(), left.clone()],
+        ];
+        for (leaf, proof) in l.into_iter().zip(proofs) {
+            let proof: Vec<Base64VecU8> = proof.into_iter().map(Base64VecU8::from).collect();
+            assert!(verify(&root, leaf, &proof));
+        }
+    }
+
+    #[test]
+    fn reject_wrong_allowance() {
+        testing_env!(VMContextBuilder::new().build());
+        let l = leaves();
+        let left = hash_pair(&l[0], &l[1]);
+        let right = hash_pair(&l[2], &l[3]);
+        let root = hash_pair(&left, &right);
+
+        let proof = vec![Base64VecU8::from(l[1].clone()), Base64VecU8::from(right)];
+        assert!(!verify(&root, leaf_hash(&"alice".to_string(), 4), &proof));
+        assert!(!verify(&root, leaf_hash(&"mallory".to_string(), 3), &proof));
+    }
+}
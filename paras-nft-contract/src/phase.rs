@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::TimestampSec;
+
+/// Who may mint during a sale phase
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PhaseEligibility {
+    /// accounts in the on-chain OG list
+    Og,
+    /// OG list plus accounts proving an allowance against the OG merkle root
+    Allowlist,
+    /// anyone
+    Public,
+}
+
+/// One window of the sale schedule, `end_time` is exclusive
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SalePhase {
+    pub phase_id: String,
+    pub start_time: TimestampSec,
+    pub end_time: TimestampSec,
+    pub price: U128,
+    pub limit_per_account: Option<u32>,
+    pub eligibility: PhaseEligibility,
+}
+
+impl SalePhase {
+    pub fn is_active(&self, now: TimestampSec) -> bool {
+        self.start_time <= now && now < self.end_time
+    }
+}
+
+/// Phases must have unique ids and be sorted by time without overlapping
+pub fn assert_valid_schedule(phases: &[SalePhase], max_price: u128) {
+    let mut phase_ids: HashSet<&String> = HashSet::new();
+    for (i, phase) in phases.iter().enumerate() {
+        assert!(
+            phase_ids.insert(&phase.phase_id),
+            "Paras: duplicate phase_id {}",
+            phase.phase_id
+        );
+        assert!(
+            phase.start_time < phase.end_time,
+            "Paras: phase {} ends before it starts",
+            phase.phase_id
+        );
+        assert!(
+            phase.price.0 < max_price,
+            "Paras: price higher than {}",
+            max_price
+        );
+        if i > 0 {
+            assert!(
+                phases[i - 1].end_time <= phase.start_time,
+                "Paras: phase {} overlaps the previous phase",
+                phase.phase_id
+            );
+        }
+    }
+}
+
+pub fn current_phase(phases: &[SalePhase], now: TimestampSec) -> Option<&SalePhase> {
+    phases.iter().find(|phase| phase.is_active(now))
+}
+
+/// The active phase, or the one that starts next, `None` once the last phase ended
+pub fn current_or_next_phase(phases: &[SalePhase], now: TimestampSec) -> Option<&SalePhase> {
+    phases.iter().find(|phase| now < phase.end_time)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_phase(phase_id: &str, start_time: TimestampSec, end_time: TimestampSec) -> SalePhase {
+        SalePhase {
+            phase_id: phase_id.to_string(),
+            start_time,
+            end_time,
+            price: U128(0),
+            limit_per_account: None,
+            eligibility: PhaseEligibility::Public,
+        }
+    }
+
+    #[test]
+    fn find_current_phase() {
+        let phases = vec![make_phase("og", 100, 200), make_phase("public", 200, 300)];
+        assert_valid_schedule(&phases, u128::MAX);
+
+        assert!(current_phase(&phases, 99).is_none());
+        assert_eq!(current_phase(&phases, 100).unwrap().phase_id, "og");
+        assert_eq!(current_phase(&phases, 200).unwrap().phase_id, "public");
+        assert!(current_phase(&phases, 300).is_none());
+    }
+
+    #[test]
+    fn find_current_or_next_phase() {
+        let phases = vec![make_phase("og", 100, 200), make_phase("public", 250, 300)];
+
+        assert_eq!(current_or_next_phase(&phases, 99).unwrap().phase_id, "og");
+        assert_eq!(current_or_next_phase(&phases, 199).unwrap().phase_id, "og");
+        assert_eq!(
+            current_or_next_phase(&phases, 200).unwrap().phase_id,
+            "public"
+        );
+        assert!(current_or_next_phase(&phases, 300).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: phase public overlaps the previous phase")]
+    fn reject_overlapping_phases() {
+        let phases = vec![make_phase("og", 100, 200), make_phase("public", 150, 300)];
+        assert_valid_schedule(&phases, u128::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Paras: duplicate phase_id og")]
+    fn reject_duplicate_phase_id() {
+        let phases = vec![make_phase("og", 100, 200), make_phase("og", 200, 300)];
+        assert_valid_schedule(&phases, u128::MAX);
+    }
+}
@@ -1,10 +1,13 @@
 use std::marker::PhantomData;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, IntoStorageKey};
 
 const ERR_INCONSISTENT_STATE: &str = "The collection is an inconsistent state. Did previous smart contract execution terminate unexpectedly?";
 pub const ERR_INDEX_OUT_OF_BOUNDS: &str = "Index out of bounds";
+pub const ERR_EMPTY: &str = "Nothing left to draw";
+pub const ERR_NOT_INDEXED: &str = "Raffle positions are still being indexed";
 
 fn expect_consistent_state<T>(val: Option<T>) -> T {
     // val.unwrap_or_else(|| env::panic_str(ERR_INCONSISTENT_STATE))
@@ -15,16 +18,41 @@ pub(crate) fn append_slice(id: &[u8], extra: &[u8]) -> Vec<u8> {
     [id, extra].concat()
 }
 
-/// This is similar to the raffle collection but doesn't keep track of past winners
+/// How `draw_and_mint` picks from the raffle pool
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RaffleMode {
+    /// every entry has the same odds and leaves the pool once drawn
+    Uniform,
+    /// entries are picked proportionally to their weight and stay until their series is minted out
+    Weighted,
+}
+
+/// This is similar to the raffle collection but doesn't keep track of past winners.
+/// Every value written to an index is also recorded under its own key, so finding
+/// it again doesn't need a scan.
 #[derive(BorshSerialize, BorshDeserialize)]
 #[cfg_attr(not(feature = "expensive-debug"), derive(Debug))]
 pub struct Raffle {
     len: u64,
     prefix: Vec<u8>,
+    /// number of draws so far, mixed into every seed
+    nonce: u64,
+    /// indexes below this have their value's position recorded, see `index_positions`
+    indexed_len: u64,
     #[borsh_skip]
     el: PhantomData<u64>,
 }
 
+/// Layout of `Raffle` in the released contract, before nonces and position lookups
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RaffleV1 {
+    pub len: u64,
+    pub prefix: Vec<u8>,
+}
+
 impl Raffle {
     /// Returns the number of elements in the vector, also referred to as its size.
     pub fn len(&self) -> u64 {
@@ -36,6 +64,11 @@ impl Raffle {
         self.len == 0
     }
 
+    /// Returns `true` once `position` can be used, see `index_positions`.
+    pub fn is_indexed(&self) -> bool {
+        self.indexed_len >= self.len
+    }
+
     /// Create new vector with zero elements. Use `id` as a unique identifier on the trie.
     pub fn new<S>(prefix: S, len: u64) -> Self
     where
@@ -44,19 +77,120 @@ impl Raffle {
         Self {
             len,
             prefix: prefix.into_storage_key(),
+            nonce: 0,
+            // fresh indexes hold their own index, which needs no lookup
+            indexed_len: len,
             el: PhantomData,
         }
     }
 
+    /// Takes over a pool written by `RaffleV1`. Its moved values have no recorded
+    /// position, so `position` panics until `index_positions` went over the whole pool.
+    pub fn from_v1(prev: RaffleV1) -> Self {
+        Self {
+            len: prev.len,
+            prefix: prev.prefix,
+            nonce: 0,
+            indexed_len: 0,
+            el: PhantomData,
+        }
+    }
+
+    /// Records the position of up to `limit` values written by `RaffleV1`,
+    /// returns how many indexes are left to go over.
+    pub fn index_positions(&mut self, limit: u64) -> u64 {
+        let end = self.len.min(self.indexed_len.saturating_add(limit));
+        for index in self.indexed_len..end {
+            if let Some(raw_value) = env::storage_read(&self.index_to_lookup_key(index)) {
+                env::storage_write(
+                    &self.value_to_position_key(u64::try_from_slice(&raw_value).unwrap()),
+                    &index.to_le_bytes()[..],
+                );
+            }
+        }
+        self.indexed_len = self.indexed_len.max(end);
+        self.len - self.indexed_len
+    }
+
+    /// Returns the nonce the next draw will be seeded with.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
     fn index_to_lookup_key(&self, index: u64) -> Vec<u8> {
         append_slice(&self.prefix, &index.to_le_bytes()[..])
     }
 
+    /// `prefix || "i" || value`, one byte longer than the index keys so they never collide
+    fn value_to_position_key(&self, value: u64) -> Vec<u8> {
+        [&self.prefix[..], b"i", &value.to_le_bytes()[..]].concat()
+    }
+
+    /// Returns the element at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: u64) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+        let value = match env::storage_read(&self.index_to_lookup_key(index)) {
+            Some(raw_value) => u64::try_from_slice(&raw_value).unwrap(),
+            // nothing stored yet, the element is still its own index
+            None => index,
+        };
+        Some(value)
+    }
+
+    /// Returns the index of `value`, or `None` if it is not in the collection.
+    /// Costs at most two storage lookups whatever the size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pool taken over by `from_v1` isn't fully indexed yet.
+    pub fn position(&self, value: u64) -> Option<u64> {
+        if self.indexed_len < self.len {
+            env::panic(ERR_NOT_INDEXED.as_bytes())
+        }
+        if let Some(raw_index) = env::storage_read(&self.value_to_position_key(value)) {
+            return Some(u64::try_from_slice(&raw_index).unwrap());
+        }
+        // values that were never moved are still at their own index
+        if value < self.len && !env::storage_has_key(&self.index_to_lookup_key(value)) {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Appends an element. It is always written to storage because the implicit value
+    /// of a fresh index may have been drawn already.
+    pub fn push(&mut self, value: u64) {
+        env::storage_write(
+            &self.index_to_lookup_key(self.len),
+            &value.to_le_bytes()[..],
+        );
+        env::storage_write(
+            &self.value_to_position_key(value),
+            &self.len.to_le_bytes()[..],
+        );
+        if self.indexed_len == self.len {
+            self.indexed_len += 1;
+        }
+        self.len += 1;
+    }
+
+    /// Removes the element at `index`, the last element takes its place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: u64) -> u64 {
+        u64::try_from_slice(&self.swap_remove_raw(index)).unwrap()
+    }
+
     /// # Panics
     ///
     /// Panics if `index` is out of bounds.
     fn swap_remove_raw(&mut self, index: u64) -> Vec<u8> {
-        if index >= self.len {
+        let raw_value = if index >= self.len {
             // env::panic_str(ERR_INDEX_OUT_OF_BOUNDS)
             env::panic(ERR_INDEX_OUT_OF_BOUNDS.as_bytes())
         } else if index + 1 == self.len {
@@ -66,13 +200,20 @@ impl Raffle {
             let raw_last_value = self
                 .pop_raw()
                 .expect("checked `index < len` above, so `len > 0`");
+            // the last value moves into the hole, its position moves with it
+            env::storage_write(
+                &self.value_to_position_key(u64::try_from_slice(&raw_last_value).unwrap()),
+                &index.to_le_bytes()[..],
+            );
             if env::storage_write(&lookup_key, &raw_last_value) {
                 expect_consistent_state(env::storage_get_evicted())
             } else {
                 // no value was at location its index is the value
                 index.to_le_bytes().to_vec()
             }
-        }
+        };
+        env::storage_remove(&self.value_to_position_key(u64::try_from_slice(&raw_value).unwrap()));
+        raw_value
     }
 
     /// Removes the last element from a vector and returns it without deserializing, or `None` if it is empty.
@@ -81,6 +222,7 @@ impl Raffle {
             None
         } else {
             self.len -= 1;
+            self.indexed_len = self.indexed_len.min(self.len);
             let last_lookup_key = self.index_to_lookup_key(self.len);
             let raw_last_value = if env::storage_remove(&last_lookup_key) {
                 // if key is en in storage, it's value will be stored as last evicted value
@@ -93,42 +235,269 @@ impl Raffle {
         }
     }
 
-    pub fn draw(&mut self) -> u64 {
-        // let seed_num = crate::util::get_random_number(0) as u64;
-        let mut seed = env::random_seed();
-        let seed_len = seed.len();
-        let mut arr: [u8; 4] = Default::default();
-        seed.rotate_left(0 as usize % seed_len);
-        arr.copy_from_slice(&seed[..4]);
-        let seed_num = u32::from_le_bytes(arr) as u64;
-        u64::try_from_slice(&self.swap_remove_raw(seed_num % self.len())).unwrap()
+    /// sha256(random_seed || nonce || entropy), the nonce makes draws in the same block differ
+    fn next_seed(&mut self, entropy: &[u8]) -> u64 {
+        let seed = env::sha256(
+            &[
+                &env::random_seed()[..],
+                &self.nonce.to_le_bytes()[..],
+                entropy,
+            ]
+            .concat(),
+        );
+        self.nonce += 1;
+        let mut arr: [u8; 8] = Default::default();
+        arr.copy_from_slice(&seed[..8]);
+        u64::from_le_bytes(arr)
     }
 
+    /// Removes a random element and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is nothing left to draw.
+    pub fn draw(&mut self, entropy: &[u8]) -> u64 {
+        if self.is_empty() {
+            env::panic(ERR_EMPTY.as_bytes())
+        }
+        let seed_num = self.next_seed(entropy);
+        self.swap_remove(seed_num % self.len())
+    }
+
+    /// Picks a random index with odds proportional to `weights[index]`, without removing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weights` doesn't match the length or every weight is 0.
+    pub fn pick_weighted(&mut self, entropy: &[u8], weights: &[u64]) -> u64 {
+        assert_eq!(
+            weights.len() as u64,
+            self.len,
+            "{}",
+            ERR_INDEX_OUT_OF_BOUNDS
+        );
+        let total: u128 = weights.iter().map(|weight| *weight as u128).sum();
+        if total == 0 {
+            env::panic(ERR_EMPTY.as_bytes())
+        }
+        let mut target = self.next_seed(entropy) as u128 % total;
+        for (index, weight) in weights.iter().enumerate() {
+            let weight = *weight as u128;
+            if target < weight {
+                return index as u64;
+            }
+            target -= weight;
+        }
+        unreachable!()
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    // use rand::{Rng, SeedableRng};
-    use rand::SeedableRng;
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
     use std::collections::HashSet;
 
-    use super::Raffle;
+    use super::{Raffle, RaffleV1};
     use near_sdk::test_utils::VMContextBuilder;
-    // use near_sdk::testing_env;
+    use near_sdk::{env, testing_env, MockedBlockchain};
+
+    /// Gives the next call a fresh random seed, keeping whatever is in storage
+    fn reseed(context: &mut VMContextBuilder, rng: &mut XorShiftRng) {
+        let storage = env::take_blockchain_interface()
+            .unwrap()
+            .as_mut_mocked_blockchain()
+            .unwrap()
+            .take_storage();
+        env::set_blockchain_interface(Box::new(MockedBlockchain::new(
+            context.random_seed(rng.gen::<[u8; 32]>().to_vec()).build(),
+            Default::default(),
+            Default::default(),
+            vec![],
+            storage,
+            Default::default(),
+            None,
+        )));
+    }
+
+    /// Every index below `len` holds a distinct value that wasn't drawn yet and is found
+    /// at that index, and nothing is left in storage past the end
+    fn assert_consistent(vec: &Raffle, drawn: &HashSet<u64>, size: u64) {
+        let mut remaining: HashSet<u64> = HashSet::new();
+        for index in 0..vec.len() {
+            let value = vec.get(index).unwrap();
+            assert!(value < size);
+            assert!(!drawn.contains(&value));
+            assert!(remaining.insert(value));
+            assert_eq!(vec.position(value), Some(index));
+        }
+        for value in drawn.iter() {
+            assert_eq!(vec.position(*value), None);
+            assert!(!env::storage_has_key(&vec.value_to_position_key(*value)));
+        }
+        assert_eq!(remaining.len() as u64 + drawn.len() as u64, size);
+        assert!(!env::storage_has_key(&vec.index_to_lookup_key(vec.len())));
+        assert!(vec.get(vec.len()).is_none());
+    }
 
     #[test]
     pub fn test_swap_remove() {
-        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(2);
+        let mut rng = XorShiftRng::seed_from_u64(2);
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.build());
         let mut vec = Raffle::new(b"v".to_vec(), 100);
         let mut set: HashSet<u64> = HashSet::new();
-        let mut context = VMContextBuilder::new();
-        // testing_env!(context.build());
         for _ in 0..100 {
+            reseed(&mut context, &mut rng);
             let len = vec.len();
-            assert!(set.insert(vec.draw()));
+            assert!(set.insert(vec.draw(&[])));
             assert_eq!(len - 1, vec.len());
-            // testing_env!(context.random_seed(rng.gen()).build());
         }
     }
+
+    #[test]
+    pub fn test_draw_until_exhausted() {
+        let mut rng = XorShiftRng::seed_from_u64(7);
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.build());
+        let size = 64;
+        let mut vec = Raffle::new(b"e".to_vec(), size);
+        let mut drawn: HashSet<u64> = HashSet::new();
+        while !vec.is_empty() {
+            reseed(&mut context, &mut rng);
+            assert!(drawn.insert(vec.draw(&[])));
+            assert_consistent(&vec, &drawn, size);
+        }
+        assert_eq!(drawn, (0..size).collect::<HashSet<u64>>());
+        assert_eq!(vec.nonce(), size);
+    }
+
+    #[test]
+    pub fn test_swap_remove_keeps_storage_consistent() {
+        let mut rng = XorShiftRng::seed_from_u64(11);
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.build());
+        let size = 32;
+        let mut vec = Raffle::new(b"s".to_vec(), size);
+        let mut drawn: HashSet<u64> = HashSet::new();
+        while !vec.is_empty() {
+            // mix removals of the last element, the first element and anything in between
+            let index = match rng.gen_range(0, 3) {
+                0 => vec.len() - 1,
+                1 => 0,
+                _ => rng.gen_range(0, vec.len()),
+            };
+            let expected = vec.get(index).unwrap();
+            assert_eq!(vec.swap_remove(index), expected);
+            drawn.insert(expected);
+            assert_consistent(&vec, &drawn, size);
+        }
+    }
+
+    #[test]
+    pub fn test_position_of_pushed_values() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut vec = Raffle::new(b"i".to_vec(), 4);
+        vec.push(10);
+        vec.push(11);
+        assert_eq!(vec.position(10), Some(4));
+        assert_eq!(vec.position(4), None);
+
+        // 11 moves into index 1, then 10 into index 0
+        assert_eq!(vec.swap_remove(1), 1);
+        assert_eq!(vec.position(11), Some(1));
+        assert_eq!(vec.swap_remove(0), 0);
+        assert_eq!(vec.position(10), Some(0));
+        assert_eq!(vec.position(0), None);
+        assert_eq!(vec.position(1), None);
+        assert_eq!(vec.position(2), Some(2));
+        assert_eq!(vec.position(3), Some(3));
+        assert_eq!(vec.len(), 4);
+    }
+
+    /// `RaffleV1` pool [4, 1, 2], value 4 was moved into index 0 by a past draw
+    fn legacy_raffle() -> Raffle {
+        let vec = Raffle::from_v1(RaffleV1 {
+            len: 3,
+            prefix: b"l".to_vec(),
+        });
+        env::storage_write(&vec.index_to_lookup_key(0), &4u64.to_le_bytes()[..]);
+        vec
+    }
+
+    #[test]
+    pub fn test_index_positions_of_legacy_pool() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut vec = legacy_raffle();
+        assert_eq!(vec.index_positions(2), 1);
+        assert_eq!(vec.index_positions(10), 0);
+
+        assert_eq!(vec.position(4), Some(0));
+        assert_eq!(vec.position(1), Some(1));
+        assert_eq!(vec.position(2), Some(2));
+        assert_eq!(vec.position(0), None);
+        assert_eq!(vec.position(3), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Raffle positions are still being indexed")]
+    pub fn test_position_of_unindexed_legacy_pool_panics() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut vec = legacy_raffle();
+        vec.index_positions(2);
+        vec.position(1);
+    }
+
+    #[test]
+    pub fn test_draw_is_uniform() {
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.build());
+        let size = 10;
+        let trials = 2000;
+        let mut counts = vec![0u64; size as usize];
+        for _ in 0..trials {
+            reseed(&mut context, &mut rng);
+            let mut vec = Raffle::new(b"u".to_vec(), size);
+            counts[vec.draw(&[]) as usize] += 1;
+            // empty the pool so the next round starts from clean storage
+            while !vec.is_empty() {
+                vec.draw(&[]);
+            }
+        }
+
+        let expected = trials as f64 / size as f64;
+        let chi_square: f64 = counts
+            .iter()
+            .map(|count| (*count as f64 - expected).powi(2) / expected)
+            .sum();
+        // 9 degrees of freedom, p = 0.001
+        assert!(
+            chi_square < 27.88,
+            "chi-square {} for {:?}",
+            chi_square,
+            counts
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing left to draw")]
+    pub fn test_draw_empty_panics() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut vec = Raffle::new(b"p".to_vec(), 1);
+        vec.draw(&[]);
+        vec.draw(&[]);
+    }
+
+    #[test]
+    pub fn test_pick_weighted_skips_zero_weight() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut vec = Raffle::new(b"w".to_vec(), 3);
+        for _ in 0..50 {
+            let index = vec.pick_weighted(&[], &[0, 5, 0]);
+            assert_eq!(index, 1);
+        }
+        assert_eq!(vec.len(), 3);
+    }
 }
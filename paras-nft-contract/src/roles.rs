@@ -0,0 +1,18 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Permissions that can be granted to other accounts, the owner implicitly holds all of them
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// every role below, plus granting and revoking them
+    Admin,
+    /// creating token series
+    Minter,
+    /// transaction fee and mint price
+    FeeManager,
+    /// OG list and OG balances
+    AllowlistManager,
+    /// pausing minting, transfers and payouts
+    Pauser,
+}
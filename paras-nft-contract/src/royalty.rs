@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use near_sdk::json_types::U128;
+use near_sdk::{AccountId, Balance};
+
+use crate::Payout;
+
+/// Royalty is expressed in basis points of the sale balance
+pub const ROYALTY_DENOMINATOR: u32 = 10_000;
+
+/// Splits `balance` between the series royalty receivers and the token owner.
+///
+/// An owner that is also a royalty receiver gets a single merged entry, and the
+/// rounding dust left by the receivers' shares goes to the owner, so the payout
+/// always adds up to `balance`. Without `max_len_payout` every receiver plus the
+/// owner is allowed.
+pub fn compute_payout(
+    royalty: &HashMap<AccountId, u32>,
+    owner_id: &AccountId,
+    balance: Balance,
+    max_len_payout: Option<u32>,
+) -> Result<Payout, String> {
+    let max_len_payout = max_len_payout.unwrap_or(royalty.len() as u32 + 1);
+
+    let total_perpetual: u32 = royalty
+        .iter()
+        .filter(|(account_id, _)| *account_id != owner_id)
+        .map(|(_, v)| *v)
+        .sum();
+    if total_perpetual > ROYALTY_DENOMINATOR {
+        return Err(format!(
+            "Paras: total royalty {} exceeds {}",
+            total_perpetual, ROYALTY_DENOMINATOR
+        ));
+    }
+
+    let mut payout: HashMap<AccountId, U128> = HashMap::new();
+    let mut total_paid: Balance = 0;
+    for (account_id, v) in royalty.iter() {
+        if account_id != owner_id {
+            let amount = royalty_to_payout(*v, balance);
+            total_paid += amount;
+            payout.insert(account_id.clone(), U128(amount));
+        }
+    }
+    payout.insert(owner_id.clone(), U128(balance - total_paid));
+
+    if payout.len() > max_len_payout as usize {
+        return Err(format!(
+            "Paras: payout to {} receivers exceeds max_len_payout {}",
+            payout.len(),
+            max_len_payout
+        ));
+    }
+
+    Ok(Payout { payout })
+}
+
+/// `a` basis points of `b`, rounded down without overflowing on large balances
+fn royalty_to_payout(a: u32, b: Balance) -> Balance {
+    let denominator = ROYALTY_DENOMINATOR as u128;
+    b / denominator * a as u128 + b % denominator * a as u128 / denominator
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn royalty(entries: &[(&str, u32)]) -> HashMap<AccountId, u32> {
+        entries
+            .iter()
+            .map(|(account_id, v)| (account_id.to_string(), *v))
+            .collect()
+    }
+
+    fn amount(payout: &Payout, account_id: &str) -> Balance {
+        payout.payout.get(account_id).unwrap().0
+    }
+
+    #[test]
+    fn split_between_receivers_and_owner() {
+        let royalty = royalty(&[("creator", 1000), ("gallery", 500)]);
+        let payout = compute_payout(&royalty, &"owner".to_string(), 10_000, Some(3)).unwrap();
+
+        assert_eq!(payout.payout.len(), 3);
+        assert_eq!(amount(&payout, "creator"), 1000);
+        assert_eq!(amount(&payout, "gallery"), 500);
+        assert_eq!(amount(&payout, "owner"), 8500);
+    }
+
+    #[test]
+    fn merge_owner_that_is_a_receiver() {
+        let royalty = royalty(&[("creator", 1000), ("owner", 500)]);
+        let payout = compute_payout(&royalty, &"owner".to_string(), 10_000, Some(2)).unwrap();
+
+        assert_eq!(payout.payout.len(), 2);
+        assert_eq!(amount(&payout, "creator"), 1000);
+        assert_eq!(amount(&payout, "owner"), 9000);
+    }
+
+    #[test]
+    fn rounding_dust_goes_to_owner() {
+        let royalty = royalty(&[("creator", 3333), ("gallery", 3333)]);
+        let payout = compute_payout(&royalty, &"owner".to_string(), 7, Some(3)).unwrap();
+
+        assert_eq!(amount(&payout, "creator"), 2);
+        assert_eq!(amount(&payout, "gallery"), 2);
+        assert_eq!(amount(&payout, "owner"), 3);
+        let total: Balance = payout.payout.values().map(|v| v.0).sum();
+        assert_eq!(total, 7);
+    }
+
+    #[test]
+    fn no_overflow_on_large_balance() {
+        let royalty = royalty(&[("creator", 1000)]);
+        let payout = compute_payout(&royalty, &"owner".to_string(), u128::MAX, Some(2)).unwrap();
+
+        assert_eq!(amount(&payout, "creator"), u128::MAX / 10);
+        assert_eq!(amount(&payout, "owner"), u128::MAX - u128::MAX / 10);
+    }
+
+    #[test]
+    fn default_max_len_payout_fits_every_receiver() {
+        let royalty = royalty(&[("creator", 1000), ("gallery", 500)]);
+        let payout = compute_payout(&royalty, &"owner".to_string(), 10_000, None).unwrap();
+
+        assert_eq!(payout.payout.len(), 3);
+        assert_eq!(amount(&payout, "owner"), 8500);
+    }
+
+    #[test]
+    fn reject_too_many_receivers() {
+        let royalty = royalty(&[("creator", 1000), ("gallery", 500)]);
+        let err = compute_payout(&royalty, &"owner".to_string(), 10_000, Some(2)).unwrap_err();
+        assert_eq!(err, "Paras: payout to 3 receivers exceeds max_len_payout 2");
+    }
+
+    #[test]
+    fn reject_royalty_overflow() {
+        let royalty = royalty(&[("creator", 6000), ("gallery", 5000)]);
+        let err = compute_payout(&royalty, &"owner".to_string(), 10_000, Some(3)).unwrap_err();
+        assert_eq!(err, "Paras: total royalty 11000 exceeds 10000");
+    }
+}
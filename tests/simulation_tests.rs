@@ -1,4 +1,5 @@
 use paras_nft_contract::ContractContract as Contract;
+use paras_nft_contract::event::{NearEvent, Nep171Event, Nep171EventKind};
 use near_sdk_sim::{
     deploy, init_simulator, to_yocto, ContractAccount, UserAccount, DEFAULT_GAS
 };
@@ -44,6 +45,19 @@ pub fn init() -> (UserAccount, ContractAccount<Contract>, UserAccount) {
     (root, nft_contract, treasury)
 }
 
+pub fn grant_minter(root: &UserAccount, nft: &ContractAccount<Contract>, account: &UserAccount) {
+    root.call(
+        nft.account_id(),
+        "grant_role",
+        &json!({
+            "account_id": account.account_id(),
+            "role": "Minter",
+        }).to_string().into_bytes(),
+        DEFAULT_GAS,
+        1
+    ).assert_success();
+}
+
 #[test]
 fn simulate_create_new_series() {
     let (root, nft, _) = init();
@@ -116,6 +130,19 @@ fn simulate_mint() {
     println!("[MINT] Storage price: {} yoctoNEAR", storage_price_for_mint);
     println!("[MINT] Gas burnt price: {} TeraGas", outcome.gas_burnt() as f64 / 1e12);
 
+    let minted_token_ids: Vec<String> = outcome
+        .logs()
+        .iter()
+        .filter_map(|log| NearEvent::from_log_line(log))
+        .flat_map(|event| match event {
+            NearEvent::Nep171(Nep171Event { event_kind: Nep171EventKind::NftMint(data), .. }) => {
+                data.into_iter().flat_map(|mint| mint.token_ids).collect::<Vec<_>>()
+            }
+            _ => vec![],
+        })
+        .collect();
+    assert_eq!(minted_token_ids, vec!["1:1".to_string()]);
+
     let initial_storage_usage = nft.account().unwrap().storage_usage;
 
     let outcome = root.call(
@@ -215,6 +242,7 @@ fn simulate_buy() {
     let (root, nft, treasury) = init();
 
     let alice = root.create_user("alice".to_string(), to_yocto("100"));
+    grant_minter(&root, &nft, &alice);
 
     let treasury_balance = treasury.account().unwrap().amount;
 
@@ -235,10 +263,13 @@ fn simulate_buy() {
         }).to_string().into_bytes(),
         DEFAULT_GAS,
         to_yocto("1")
-    );
+    ).assert_success();
 
     let alice_balance = alice.account().unwrap().amount;
+    let root_balance = root.account().unwrap().amount;
+    let initial_storage_usage = nft.account().unwrap().storage_usage;
 
+    // one NEAR more than price and storage, it comes back as a refund
     root.call(
         nft.account_id(),
         "nft_buy",
@@ -247,8 +278,8 @@ fn simulate_buy() {
             "receiver_id": root.account_id(),
         }).to_string().into_bytes(),
         DEFAULT_GAS,
-        to_yocto("1") + STORAGE_MINT_ESTIMATE
-    );
+        to_yocto("2") + STORAGE_MINT_ESTIMATE
+    ).assert_success();
 
     let for_treasury = (to_yocto("1") * 500) / 10_000;
     let for_seller = to_yocto("1") - for_treasury;
@@ -258,6 +289,13 @@ fn simulate_buy() {
 
     assert_eq!(for_treasury, diff_after_sell_treasury);
     assert_eq!(for_seller, diff_after_sell_alice);
+
+    // the buyer pays price, storage and gas, nothing of the extra NEAR
+    let storage_cost =
+        (nft.account().unwrap().storage_usage - initial_storage_usage) as u128 * 10u128.pow(19);
+    let spent_by_root = root_balance - root.account().unwrap().amount;
+    assert!(spent_by_root >= to_yocto("1") + storage_cost);
+    assert!(spent_by_root - to_yocto("1") - storage_cost < to_yocto("0.1"));
 }
 
 #[test]
@@ -265,6 +303,7 @@ fn simulate_buy_change_transaction_fee() {
     let (root, nft, treasury) = init();
 
     let alice = root.create_user("alice".to_string(), to_yocto("100"));
+    grant_minter(&root, &nft, &alice);
 
     let treasury_balance = treasury.account().unwrap().amount;
 
@@ -285,7 +324,7 @@ fn simulate_buy_change_transaction_fee() {
         }).to_string().into_bytes(),
         DEFAULT_GAS,
         to_yocto("1")
-    );
+    ).assert_success();
 
     let alice_balance = alice.account().unwrap().amount;
 
@@ -308,7 +347,7 @@ fn simulate_buy_change_transaction_fee() {
         }).to_string().into_bytes(),
         DEFAULT_GAS,
         to_yocto("1") + STORAGE_MINT_ESTIMATE
-    );
+    ).assert_success();
 
     // the transaction fee still 500 (locked transaction fee)
     let for_treasury = (to_yocto("1") * 500) / 10_000;